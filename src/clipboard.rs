@@ -1,49 +1,513 @@
-use arboard::Clipboard;
-use std::{sync::Mutex, thread, time::Duration};
+use std::{
+    env,
+    sync::{mpsc::Sender, Mutex, MutexGuard},
+    thread,
+    time::{Duration, Instant},
+};
 
-use crate::Error;
+use crate::{Error, PasswordEvent};
 
-static CLIPBOARD: Mutex<Option<Clipboard>> = Mutex::new(None);
-const EXPIRATION_INTERVAL: u64 = 45;
+mod osc52;
+mod paste_once;
+#[cfg(feature = "system-clipboard")]
+mod system;
+mod termux;
+
+pub use osc52::Osc52Clipboard;
+#[cfg(feature = "system-clipboard")]
+pub use system::SystemClipboard;
+pub use termux::TermuxClipboard;
+
+static CLIPBOARD: Mutex<ClipboardState> = Mutex::new(ClipboardState {
+    backend: ClipboardBackend::Auto,
+    provider: None,
+    secret: None,
+});
+/// Time after which secrets are cleared from the clipboard, like in pass.
+const DEFAULT_EXPIRY: Duration = Duration::from_secs(45);
+/// Interval of [`PasswordEvent::ClipboardCountdown`] events.
+const COUNTDOWN_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Options for copying to the clipboard.
+#[derive(Debug, Clone)]
+pub struct ClipboardOptions {
+    /// Time after which copied secrets like passwords are cleared, defaults to
+    /// `PASSWORD_STORE_CLIP_TIME` seconds or 45 seconds
+    ///
+    /// `None` keeps secrets on the clipboard until they are replaced or cleared with
+    /// [`clear_now`](crate::clear_now).
+    pub expiry: Option<Duration>,
+    /// Selection to copy to, defaults to `PASSWORD_STORE_X_SELECTION` or the clipboard
+    pub selection: Selection,
+    /// Serve secrets for a single paste only, after which the selection is empty
+    ///
+    /// This is only supported on Wayland and X11 and requires `wl-copy` or `xclip`
+    /// respectively. Secrets are still cleared after the expiry if they were not
+    /// pasted in the meantime.
+    pub paste_once: bool,
+    /// Keep one-time passwords copied with [`copy_otp`](crate::copy_otp) up to date,
+    /// replacing the code whenever a new period starts, until the expiry has passed
+    /// and the clipboard is cleared
+    ///
+    /// Otherwise one-time passwords stay on the clipboard, even after they expired.
+    pub refresh_otp: bool,
+    /// Password ID of the copied entry and the channel that receives
+    /// [`PasswordEvent::ClipboardCountdown`] ticks and a
+    /// [`PasswordEvent::ClipboardCleared`] when the expiry has passed
+    pub event_tx: Option<(String, Sender<PasswordEvent>)>,
+}
+
+impl ClipboardOptions {
+    /// Sends [`PasswordEvent::ClipboardCountdown`] ticks while a secret of the
+    /// entry is on the clipboard and a [`PasswordEvent::ClipboardCleared`] once it
+    /// was cleared.
+    pub fn with_event_sender(
+        mut self,
+        pass_id: impl Into<String>,
+        event_tx: Sender<PasswordEvent>,
+    ) -> Self {
+        self.event_tx = Some((pass_id.into(), event_tx));
+        self
+    }
+}
+
+impl Default for ClipboardOptions {
+    fn default() -> Self {
+        let expiry = env::var("PASSWORD_STORE_CLIP_TIME")
+            .ok()
+            .and_then(|seconds| seconds.trim().parse().ok())
+            .filter(|seconds| *seconds > 0)
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_EXPIRY);
+        let selection = match env::var("PASSWORD_STORE_X_SELECTION").as_deref() {
+            Ok("primary") => Selection::Primary,
+            Ok("secondary") => Selection::Secondary,
+            _ => Selection::Clipboard,
+        };
+        Self {
+            expiry: Some(expiry),
+            selection,
+            paste_once: false,
+            refresh_otp: false,
+            event_tx: None,
+        }
+    }
+}
+
+/// The X11 or Wayland selection that text is copied to.
+///
+/// Other platforms only have a clipboard, which is used for all selections.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Selection {
+    /// The regular clipboard for explicit copy and paste
+    #[default]
+    Clipboard,
+    /// The selection pasted with a middle click, which requires a Wayland
+    /// compositor supporting it
+    Primary,
+    /// Rarely used and not available on Wayland
+    Secondary,
+}
+
+/// Access to a clipboard, implemented for the system clipboard and OSC 52.
+///
+/// Embedders can provide their own implementation with
+/// [`ClipboardBackend::Custom`], e.g. for a remote clipboard or in tests.
+pub trait ClipboardProvider: Send {
+    /// Places the text on the selection, sensitive text like passwords should be
+    /// excluded from clipboard history if possible.
+    fn set_text(&mut self, text: &str, selection: Selection, sensitive: bool) -> Result<(), Error>;
+
+    /// Returns the text on the selection, `None` if the clipboard cannot be read.
+    fn get_text(&mut self, selection: Selection) -> Result<Option<String>, Error>;
+
+    fn clear(&mut self, selection: Selection) -> Result<(), Error>;
+}
+
+/// How text is placed on the clipboard.
+#[derive(Default)]
+pub enum ClipboardBackend {
+    /// The Android clipboard in Termux, otherwise the system clipboard, or OSC 52
+    /// if it is not available and there is a terminal, e.g. in an SSH session
+    /// without display
+    #[default]
+    Auto,
+    /// The system clipboard of X11, Wayland, macOS or Windows, requires the
+    /// `system-clipboard` feature
+    System,
+    /// OSC 52 escape sequences written to the controlling terminal, which sets the
+    /// clipboard of the terminal emulator, also through SSH and tmux
+    ///
+    /// The clipboard cannot be read, so expiring secrets are cleared even if they
+    /// were replaced in the meantime.
+    Osc52,
+    /// The Android clipboard through the Termux:API commands
+    Termux,
+    Custom(Box<dyn ClipboardProvider>),
+}
+
+/// Selects how text is placed on the clipboard for the whole process.
+pub fn set_clipboard_backend(backend: ClipboardBackend) {
+    if let Ok(mut clipboard) = lock_state().or_else(|_| lock_state()) {
+        clipboard.provider = None;
+        clipboard.backend = backend;
+    }
+}
+
+struct ClipboardState {
+    backend: ClipboardBackend,
+    /// Provider of the built-in backends, created on first use
+    provider: Option<Box<dyn ClipboardProvider>>,
+    /// Secret that was last placed on the clipboard by this library
+    secret: Option<(String, Selection)>,
+}
 
 /// Schedules clearing of the clipboard after the specified duration,
 /// but only if the clipboard still contains the specified text.
-fn schedule_clipboard_clear(text: String, expiry_seconds: u64) {
+///
+/// Countdown events are sent every second until then. The cleared event is sent
+/// either way, since the secret is no longer on the clipboard.
+fn schedule_clipboard_clear(
+    text: String,
+    expiry: Duration,
+    event_tx: Option<(String, Sender<PasswordEvent>)>,
+) {
     thread::spawn(move || {
-        thread::sleep(Duration::from_secs(expiry_seconds));
-
-        // Get clipboard
-        let mut clipboard = CLIPBOARD
-            .lock()
-            .expect("another thread holding the lock paniced");
-
-        // Clear clipboard
-        if let Some(ref mut clipboard_instance) = *clipboard {
-            if let Ok(current_text) = clipboard_instance.get_text() {
-                if current_text == text {
-                    let _ = clipboard_instance.clear();
+        let deadline = Instant::now() + expiry;
+        match event_tx {
+            Some((_, ref event_tx)) => loop {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    break;
                 }
+                // Ticks stop when the secret was replaced, it has its own countdown
+                let current = CLIPBOARD.lock().is_ok_and(|clipboard| {
+                    clipboard.secret.as_ref().map(|(secret, _)| secret) == Some(&text)
+                });
+                if !current
+                    || event_tx
+                        .send(PasswordEvent::ClipboardCountdown { remaining })
+                        .is_err()
+                {
+                    thread::sleep(remaining);
+                    break;
+                }
+                thread::sleep(remaining.min(COUNTDOWN_INTERVAL));
+            },
+            None => thread::sleep(expiry),
+        }
+
+        // Get clipboard, the state is reset once after a poisoned lock
+        if let Ok(mut clipboard) = lock_state().or_else(|_| lock_state()) {
+            // Clear clipboard
+            if clipboard.secret.as_ref().map(|(secret, _)| secret) == Some(&text) {
+                clipboard.clear_secret();
             }
         }
+
+        if let Some((pass_id, event_tx)) = event_tx {
+            let _ = event_tx.send(PasswordEvent::ClipboardCleared { pass_id });
+        }
     });
 }
 
-pub fn copy_to_clipboard(text: &str, expires: bool) -> Result<(), Error> {
+/// Locks the clipboard state.
+///
+/// If another thread panicked while holding the lock, the poisoning is cleared and
+/// the clipboard provider is dropped to be reinitialized on the next use, since it
+/// may have been left in an inconsistent state. This is reported as an error once.
+fn lock_state() -> Result<MutexGuard<'static, ClipboardState>, Error> {
+    CLIPBOARD.lock().map_err(|poisoned| {
+        CLIPBOARD.clear_poison();
+        poisoned.into_inner().provider = None;
+        Error::ClipboardPoisoned
+    })
+}
+
+impl ClipboardState {
+    /// Returns the provider of the backend, initializing it when not already
+    /// initialized.
+    fn provider(&mut self) -> Result<&mut dyn ClipboardProvider, Error> {
+        if let ClipboardBackend::Custom(ref mut provider) = self.backend {
+            return Ok(provider.as_mut());
+        }
+        if self.provider.is_none() {
+            let provider: Box<dyn ClipboardProvider> = match self.backend {
+                ClipboardBackend::Auto if termux::available() => Box::new(TermuxClipboard),
+                ClipboardBackend::Auto => match system_clipboard() {
+                    Ok(system) => system,
+                    Err(_) if osc52::available() => Box::new(Osc52Clipboard),
+                    Err(e) => return Err(e),
+                },
+                ClipboardBackend::System => system_clipboard()?,
+                ClipboardBackend::Osc52 => Box::new(Osc52Clipboard),
+                ClipboardBackend::Termux => Box::new(TermuxClipboard),
+                ClipboardBackend::Custom(_) => unreachable!("custom providers are returned above"),
+            };
+            self.provider = Some(provider);
+        }
+        Ok(self
+            .provider
+            .as_deref_mut()
+            .expect("clipboard should be initialized"))
+    }
+
+    /// Clears the clipboard if it still contains the secret placed by this library.
+    fn clear_secret(&mut self) {
+        let Some((secret, selection)) = self.secret.take() else {
+            return;
+        };
+        if let Ok(provider) = self.provider() {
+            match provider.get_text(selection) {
+                Ok(Some(current_text)) if current_text != secret => {}
+                Ok(_) => {
+                    let _ = provider.clear(selection);
+                }
+                Err(_) => {}
+            }
+        }
+    }
+}
+
+#[cfg(feature = "system-clipboard")]
+fn system_clipboard() -> Result<Box<dyn ClipboardProvider>, Error> {
+    Ok(Box::new(SystemClipboard::new()?))
+}
+
+#[cfg(not(feature = "system-clipboard"))]
+fn system_clipboard() -> Result<Box<dyn ClipboardProvider>, Error> {
+    Err(Error::ClipboardUnsupported)
+}
+
+/// Immediately clears any secret placed on the clipboard by this library, e.g. for
+/// a panic button or when the application shuts down.
+///
+/// Text that was replaced on the clipboard in the meantime is left untouched.
+pub fn clear_now() {
+    if let Ok(mut clipboard) = lock_state().or_else(|_| lock_state()) {
+        clipboard.clear_secret();
+    }
+}
+
+/// Clears any secret placed on the clipboard by this library when dropped.
+///
+/// Expiring secrets are cleared by a background thread, which is killed when the
+/// process exits before the expiry. Keep a guard alive in `main` to clear them on
+/// shutdown instead, also when unwinding from a panic:
+///
+/// ```no_run
+/// let _clipboard = passepartout::ClipboardGuard::new();
+/// // Run the application
+/// ```
+#[must_use = "the clipboard is cleared when the guard is dropped"]
+#[derive(Debug, Default)]
+pub struct ClipboardGuard {
+    _private: (),
+}
+
+impl ClipboardGuard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Drop for ClipboardGuard {
+    fn drop(&mut self) {
+        clear_now();
+    }
+}
+
+/// Kind of text placed on the clipboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Content {
+    /// Text that is not secret, like a password ID
+    Text,
+    /// A secret that is cleared after the expiry, like a password
+    ExpiringSecret,
+}
+
+/// Places the text on the selection of the options.
+///
+/// Secrets are marked to be excluded from clipboard history, which is respected by
+/// clipboard managers like Klipper and CopyQ and by Windows and macOS. Expiring
+/// secrets are cleared after the expiry unless replaced.
+pub fn copy_to_clipboard(
+    text: &str,
+    options: &ClipboardOptions,
+    content: Content,
+) -> Result<(), Error> {
+    let selection = options.selection;
     // Get clipboard
-    let mut clipboard = CLIPBOARD
-        .lock()
-        .expect("another thread holding the lock paniced");
+    let mut clipboard = lock_state()?;
 
-    // Initialize clipboard when not already initialized
-    if clipboard.is_none() {
-        *clipboard = Some(Clipboard::new()?);
+    if options.paste_once && content != Content::Text {
+        paste_once::set_text(text, selection)?;
+    } else {
+        clipboard
+            .provider()?
+            .set_text(text, selection, content != Content::Text)?;
     }
-    let clipboard_instance = clipboard.as_mut().expect("clipboard should be initialized");
+    clipboard.secret = match content {
+        Content::Text => None,
+        Content::ExpiringSecret => {
+            if let Some(expiry) = options.expiry {
+                schedule_clipboard_clear(text.to_string(), expiry, options.event_tx.clone());
+            }
+            Some((text.to_string(), selection))
+        }
+    };
 
-    clipboard_instance.set_text(text)?;
-    if expires {
-        schedule_clipboard_clear(text.to_string(), EXPIRATION_INTERVAL);
+    Ok(())
+}
+
+/// Places codes on the clipboard until the expiry of the options has passed and
+/// clears it afterwards.
+///
+/// `next` returns the current code and the time until it changes. Updating stops
+/// when the clipboard no longer contains a code placed here, without expiry this is
+/// the only way it stops.
+pub(crate) fn copy_rotating<F>(options: &ClipboardOptions, mut next: F) -> Result<(), Error>
+where
+    F: FnMut() -> Result<(String, Duration), Error> + Send + 'static,
+{
+    let selection = options.selection;
+    let (mut text, mut valid_for) = next()?;
+    {
+        let mut clipboard = lock_state()?;
+        clipboard.provider()?.set_text(&text, selection, true)?;
+        clipboard.secret = Some((text.clone(), selection));
     }
 
+    let deadline = options.expiry.map(|expiry| Instant::now() + expiry);
+    let event_tx = options.event_tx.clone();
+    thread::spawn(move || {
+        loop {
+            let remaining =
+                deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+            if remaining.is_some_and(|remaining| valid_for >= remaining) {
+                break;
+            }
+            thread::sleep(valid_for);
+            let Ok((code, expires)) = next() else {
+                break;
+            };
+            let Ok(mut clipboard) = lock_state() else {
+                return;
+            };
+            if clipboard.secret.as_ref().map(|(secret, _)| secret) != Some(&text) {
+                return;
+            }
+            if let Ok(provider) = clipboard.provider() {
+                let _ = provider.set_text(&code, selection, true);
+            }
+            clipboard.secret = Some((code.clone(), selection));
+            (text, valid_for) = (code, expires);
+        }
+        if let Some(deadline) = deadline {
+            schedule_clipboard_clear(
+                text,
+                deadline.saturating_duration_since(Instant::now()),
+                event_tx,
+            );
+        }
+    });
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    /// Serializes the tests, which replace the process-wide backend.
+    static BACKEND: Mutex<()> = Mutex::new(());
+
+    /// Clipboard that stores the text of the regular selection in memory.
+    struct Memory(Arc<Mutex<Option<String>>>);
+
+    impl ClipboardProvider for Memory {
+        fn set_text(&mut self, text: &str, _: Selection, _: bool) -> Result<(), Error> {
+            *self.0.lock().unwrap() = Some(text.to_string());
+            Ok(())
+        }
+
+        fn get_text(&mut self, _: Selection) -> Result<Option<String>, Error> {
+            Ok(self.0.lock().unwrap().clone())
+        }
+
+        fn clear(&mut self, _: Selection) -> Result<(), Error> {
+            *self.0.lock().unwrap() = None;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn custom_provider_is_cleared() {
+        let _guard = BACKEND.lock().unwrap();
+        let contents = Arc::new(Mutex::new(None));
+        set_clipboard_backend(ClipboardBackend::Custom(Box::new(Memory(contents.clone()))));
+        let options = ClipboardOptions {
+            expiry: Some(Duration::from_secs(60)),
+            ..Default::default()
+        };
+
+        copy_to_clipboard("hunter2", &options, Content::ExpiringSecret).unwrap();
+        assert_eq!(contents.lock().unwrap().as_deref(), Some("hunter2"));
+        clear_now();
+        assert_eq!(*contents.lock().unwrap(), None);
+
+        set_clipboard_backend(ClipboardBackend::Auto);
+    }
+
+    #[test]
+    fn cleared_event_is_sent() {
+        let _guard = BACKEND.lock().unwrap();
+        let contents = Arc::new(Mutex::new(None));
+        set_clipboard_backend(ClipboardBackend::Custom(Box::new(Memory(contents.clone()))));
+        let (event_tx, event_rx) = std::sync::mpsc::channel();
+        let options = ClipboardOptions {
+            expiry: Some(Duration::from_millis(10)),
+            ..Default::default()
+        }
+        .with_event_sender("example", event_tx);
+
+        copy_to_clipboard("hunter2", &options, Content::ExpiringSecret).unwrap();
+        let event = event_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(matches!(event, PasswordEvent::ClipboardCountdown { .. }));
+        let event = event_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(
+            matches!(event, PasswordEvent::ClipboardCleared { pass_id } if pass_id == "example")
+        );
+
+        set_clipboard_backend(ClipboardBackend::Auto);
+    }
+
+    #[test]
+    fn rotating_codes_are_replaced() {
+        let _guard = BACKEND.lock().unwrap();
+        let contents = Arc::new(Mutex::new(None));
+        set_clipboard_backend(ClipboardBackend::Custom(Box::new(Memory(contents.clone()))));
+        let (event_tx, event_rx) = std::sync::mpsc::channel();
+        let options = ClipboardOptions {
+            expiry: Some(Duration::from_millis(100)),
+            ..Default::default()
+        }
+        .with_event_sender("example", event_tx);
+
+        let mut counter = 0;
+        copy_rotating(&options, move || {
+            counter += 1;
+            Ok((counter.to_string(), Duration::from_millis(20)))
+        })
+        .unwrap();
+        assert_eq!(contents.lock().unwrap().as_deref(), Some("1"));
+        while !matches!(
+            event_rx.recv_timeout(Duration::from_secs(5)).unwrap(),
+            PasswordEvent::ClipboardCleared { .. }
+        ) {}
+        assert_eq!(*contents.lock().unwrap(), None);
+
+        set_clipboard_backend(ClipboardBackend::Auto);
+    }
+}
@@ -0,0 +1,94 @@
+use std::{env, fs::OpenOptions, io::Write};
+
+use super::{ClipboardProvider, Selection};
+use crate::Error;
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Checks whether there is a controlling terminal to write the escape sequence to.
+pub fn available() -> bool {
+    open_tty().is_ok()
+}
+
+/// Sets the clipboard of the terminal emulator with OSC 52 escape sequences written
+/// to the controlling terminal.
+///
+/// The sequences are passed through tmux and GNU screen, which need
+/// `set-clipboard` or `allow-passthrough` enabled to forward them. The clipboard
+/// cannot be read and is cleared with an empty payload, which not all terminal
+/// emulators support.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Osc52Clipboard;
+
+impl ClipboardProvider for Osc52Clipboard {
+    fn set_text(
+        &mut self,
+        text: &str,
+        selection: Selection,
+        _sensitive: bool,
+    ) -> Result<(), Error> {
+        open_tty()?.write_all(sequence(text, selection).as_bytes())?;
+        Ok(())
+    }
+
+    fn get_text(&mut self, _selection: Selection) -> Result<Option<String>, Error> {
+        Ok(None)
+    }
+
+    fn clear(&mut self, selection: Selection) -> Result<(), Error> {
+        self.set_text("", selection, false)
+    }
+}
+
+fn open_tty() -> std::io::Result<std::fs::File> {
+    OpenOptions::new().write(true).open("/dev/tty")
+}
+
+fn sequence(text: &str, selection: Selection) -> String {
+    let target = match selection {
+        Selection::Clipboard => 'c',
+        Selection::Primary => 'p',
+        Selection::Secondary => 's',
+    };
+    let osc = format!("\x1b]52;{target};{}\x07", encode(text.as_bytes()));
+    if env::var_os("TMUX").is_some() {
+        format!("\x1bPtmux;{}\x1b\\", osc.replace('\x1b', "\x1b\x1b"))
+    } else if env::var("TERM").is_ok_and(|term| term.starts_with("screen")) {
+        format!("\x1bP{osc}\x1b\\")
+    } else {
+        osc
+    }
+}
+
+fn encode(data: &[u8]) -> String {
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let bits = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(BASE64[(bits >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64_encoding() {
+        assert_eq!(encode(b""), "");
+        assert_eq!(encode(b"f"), "Zg==");
+        assert_eq!(encode(b"fo"), "Zm8=");
+        assert_eq!(encode(b"foobar"), "Zm9vYmFy");
+    }
+}
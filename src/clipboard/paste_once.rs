@@ -0,0 +1,60 @@
+use std::{
+    env,
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use super::Selection;
+use crate::Error;
+
+/// Serves the text for exactly one paste with `wl-copy` on Wayland or `xclip` on
+/// X11, which own the selection in a background process until it is pasted.
+pub fn set_text(text: &str, selection: Selection) -> Result<(), Error> {
+    let mut command = if env::var_os("WAYLAND_DISPLAY").is_some() {
+        let mut command = Command::new("wl-copy");
+        command.arg("--paste-once");
+        match selection {
+            Selection::Clipboard => {}
+            Selection::Primary => {
+                command.arg("--primary");
+            }
+            Selection::Secondary => {
+                return Err(Error::Pass(
+                    "the secondary selection is not available on Wayland".to_string(),
+                ))
+            }
+        }
+        command
+    } else if env::var_os("DISPLAY").is_some() {
+        let mut command = Command::new("xclip");
+        let target = match selection {
+            Selection::Clipboard => "clipboard",
+            Selection::Primary => "primary",
+            Selection::Secondary => "secondary",
+        };
+        command.args(["-selection", target, "-loops", "1"]);
+        command
+    } else {
+        return Err(Error::Pass(
+            "paste-once requires a Wayland or X11 display".to_string(),
+        ));
+    };
+
+    let program = command.get_program().to_string_lossy().into_owned();
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| Error::Pass(format!("paste-once requires {program}: {e}")))?;
+    child
+        .stdin
+        .take()
+        .expect("stdin should be piped")
+        .write_all(text.as_bytes())?;
+    // Both tools fork into the background once they have read the text
+    if !child.wait()?.success() {
+        return Err(Error::Pass(format!("{program} failed")));
+    }
+    Ok(())
+}
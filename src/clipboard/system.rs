@@ -0,0 +1,123 @@
+use arboard::Clipboard;
+
+use super::{ClipboardProvider, Selection};
+use crate::Error;
+
+/// The system clipboard of X11, Wayland, macOS or Windows, using arboard.
+pub struct SystemClipboard(Clipboard);
+
+impl SystemClipboard {
+    pub fn new() -> Result<Self, Error> {
+        Ok(Self(Clipboard::new()?))
+    }
+}
+
+impl ClipboardProvider for SystemClipboard {
+    fn set_text(&mut self, text: &str, selection: Selection, sensitive: bool) -> Result<(), Error> {
+        Ok(platform::set_text(&mut self.0, text, selection, sensitive)?)
+    }
+
+    fn get_text(&mut self, selection: Selection) -> Result<Option<String>, Error> {
+        Ok(Some(platform::get_text(&mut self.0, selection)?))
+    }
+
+    fn clear(&mut self, selection: Selection) -> Result<(), Error> {
+        Ok(platform::clear(&mut self.0, selection)?)
+    }
+}
+
+#[cfg(all(
+    unix,
+    not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))
+))]
+mod platform {
+    use arboard::{ClearExtLinux, Clipboard, GetExtLinux, LinuxClipboardKind, SetExtLinux};
+
+    use super::Selection;
+
+    fn kind(selection: Selection) -> LinuxClipboardKind {
+        match selection {
+            Selection::Clipboard => LinuxClipboardKind::Clipboard,
+            Selection::Primary => LinuxClipboardKind::Primary,
+            Selection::Secondary => LinuxClipboardKind::Secondary,
+        }
+    }
+
+    /// Sensitive text is marked with the `x-kde-passwordManagerHint` MIME type.
+    pub fn set_text(
+        clipboard: &mut Clipboard,
+        text: &str,
+        selection: Selection,
+        sensitive: bool,
+    ) -> Result<(), arboard::Error> {
+        let set = clipboard.set().clipboard(kind(selection));
+        if sensitive {
+            set.exclude_from_history().text(text)
+        } else {
+            set.text(text)
+        }
+    }
+
+    pub fn get_text(
+        clipboard: &mut Clipboard,
+        selection: Selection,
+    ) -> Result<String, arboard::Error> {
+        clipboard.get().clipboard(kind(selection)).text()
+    }
+
+    pub fn clear(clipboard: &mut Clipboard, selection: Selection) -> Result<(), arboard::Error> {
+        clipboard.clear_with().clipboard(kind(selection))
+    }
+}
+
+#[cfg(not(all(
+    unix,
+    not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))
+)))]
+mod platform {
+    use arboard::Clipboard;
+
+    use super::Selection;
+
+    pub fn set_text(
+        clipboard: &mut Clipboard,
+        text: &str,
+        _selection: Selection,
+        sensitive: bool,
+    ) -> Result<(), arboard::Error> {
+        let set = clipboard.set();
+        #[cfg(target_os = "macos")]
+        let set = {
+            use arboard::SetExtApple;
+            if sensitive {
+                set.exclude_from_history()
+            } else {
+                set
+            }
+        };
+        // Excludes the text from the clipboard history and the cloud clipboard
+        #[cfg(windows)]
+        let set = {
+            use arboard::SetExtWindows;
+            if sensitive {
+                set.exclude_from_monitoring()
+            } else {
+                set
+            }
+        };
+        #[cfg(not(any(target_os = "macos", windows)))]
+        let _ = sensitive;
+        set.text(text)
+    }
+
+    pub fn get_text(
+        clipboard: &mut Clipboard,
+        _selection: Selection,
+    ) -> Result<String, arboard::Error> {
+        clipboard.get_text()
+    }
+
+    pub fn clear(clipboard: &mut Clipboard, _selection: Selection) -> Result<(), arboard::Error> {
+        clipboard.clear()
+    }
+}
@@ -0,0 +1,61 @@
+use std::{
+    env,
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use super::{ClipboardProvider, Selection};
+use crate::Error;
+
+/// Checks whether this process runs in Termux on Android.
+pub fn available() -> bool {
+    env::var_os("TERMUX_VERSION").is_some()
+}
+
+/// Accesses the Android clipboard with `termux-clipboard-set` and
+/// `termux-clipboard-get` from the Termux:API package.
+///
+/// Android only has a clipboard, which is used for all selections.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TermuxClipboard;
+
+impl ClipboardProvider for TermuxClipboard {
+    fn set_text(
+        &mut self,
+        text: &str,
+        _selection: Selection,
+        _sensitive: bool,
+    ) -> Result<(), Error> {
+        // The text is passed on stdin, so that secrets do not show up in `ps`
+        let mut child = Command::new("termux-clipboard-set")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| Error::Pass(format!("failed to run termux-clipboard-set: {e}")))?;
+        child
+            .stdin
+            .take()
+            .expect("stdin should be piped")
+            .write_all(text.as_bytes())?;
+        if !child.wait()?.success() {
+            return Err(Error::Pass("termux-clipboard-set failed".to_string()));
+        }
+        Ok(())
+    }
+
+    fn get_text(&mut self, _selection: Selection) -> Result<Option<String>, Error> {
+        let output = Command::new("termux-clipboard-get")
+            .stderr(Stdio::null())
+            .output()
+            .map_err(|e| Error::Pass(format!("failed to run termux-clipboard-get: {e}")))?;
+        if !output.status.success() {
+            return Ok(None);
+        }
+        Ok(Some(String::from_utf8(output.stdout)?))
+    }
+
+    fn clear(&mut self, selection: Selection) -> Result<(), Error> {
+        self.set_text("", selection, false)
+    }
+}
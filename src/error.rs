@@ -8,15 +8,37 @@ pub enum Error {
     #[error("gpgme error: {0}")]
     Gpgme(#[from] gpgme::Error),
 
+    #[cfg(feature = "system-clipboard")]
     #[error("Clipboard error: {0}")]
     Clipboard(#[from] arboard::Error),
 
+    #[error("Clipboard error: no clipboard is available")]
+    ClipboardUnsupported,
+
+    #[error("Clipboard error: state was poisoned by a panic and has been reset")]
+    ClipboardPoisoned,
+
+    #[error("Signature verification error: no valid signature of {}", .0.display())]
+    SignatureVerification(std::path::PathBuf),
+
+    #[error("Store error: the store at {} is not initialized, it has no .gpg-id file", .path.display())]
+    StoreNotInitialized { path: std::path::PathBuf },
+
+    #[error("Timeout error: the operation did not complete in time")]
+    Timeout,
+
+    #[error("Cancelled: the operation was cancelled")]
+    Cancelled,
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
     #[error("InvalidUtf8 error: {0}")]
     InvalidUtf8(#[from] std::string::FromUtf8Error),
 
+    #[error("TOML error: {0}")]
+    Toml(#[from] toml::de::Error),
+
     #[error("OTP error: {0}")]
     Otp(#[from] totp_rs::TotpUrlError),
 }
@@ -0,0 +1,63 @@
+use std::{path::PathBuf, time::Duration};
+
+use crate::pass::{PasswordInfo, ScoredMatch};
+
+/// Events emitted by long-running or background operations.
+///
+/// Frontends receive these through a [`std::sync::mpsc::Sender`] passed to the
+/// respective operation and can update their views without polling.
+#[derive(Debug, Clone)]
+pub enum PasswordEvent {
+    /// Ranked results of a [`SearchSession`](crate::SearchSession) update.
+    ///
+    /// The generation increases with every update, results of older generations
+    /// can be discarded.
+    SearchResults {
+        generation: u64,
+        query: String,
+        matches: Vec<ScoredMatch>,
+    },
+    /// The store was locked, views showing sensitive data should be cleared.
+    Locked,
+    /// An entry was renamed, `info` contains the updated entry.
+    EntryRenamed {
+        old_id: String,
+        info: Box<PasswordInfo>,
+    },
+    /// An entry is accessible by the group or others, `mode` contains its permissions.
+    InsecurePermissions { pass_id: String, mode: u32 },
+    /// Decrypting an entry needs a hardware token like a YubiKey, which may wait for
+    /// the user to touch it.
+    HardwareTouchRequired { pass_id: String },
+    /// The login of an entry was copied to the clipboard by
+    /// [`copy_login_then_password`](crate::PasswordStore::copy_login_then_password).
+    LoginCopied { pass_id: String },
+    /// The password of an entry was copied to the clipboard after its login.
+    PasswordCopied { pass_id: String },
+    /// A secret is on the clipboard and will be cleared in `remaining`, sent every
+    /// second.
+    ClipboardCountdown { remaining: Duration },
+    /// The expiry of a secret copied with
+    /// [`ClipboardOptions::with_event_sender`](crate::ClipboardOptions::with_event_sender)
+    /// has passed, it was cleared unless the clipboard changed in the meantime.
+    ClipboardCleared { pass_id: String },
+    /// A directory in the store is a symbolic link and was not scanned, see
+    /// [`SymlinkPolicy::Report`](crate::SymlinkPolicy::Report).
+    SymlinkSkipped { path: PathBuf },
+    /// A directory in the store could not be read while scanning, its entries are
+    /// missing.
+    DirectorySkipped { path: PathBuf, error: String },
+    /// An entry was searched by [`grep`](crate::PasswordStore::grep), `done` of
+    /// `total` entries are finished.
+    GrepProgress {
+        pass_id: String,
+        done: usize,
+        total: usize,
+    },
+    /// An entry was re-encrypted, `done` of `total` entries are finished.
+    ReencryptionProgress {
+        pass_id: String,
+        done: usize,
+        total: usize,
+    },
+}
@@ -2,12 +2,34 @@
 
 mod clipboard;
 mod error;
+mod events;
 mod pass;
 
+#[cfg(feature = "system-clipboard")]
+pub use clipboard::SystemClipboard;
+pub use clipboard::{
+    clear_now, set_clipboard_backend, ClipboardBackend, ClipboardGuard, ClipboardOptions,
+    ClipboardProvider, Osc52Clipboard, Selection, TermuxClipboard,
+};
 pub use error::Error;
+pub use events::PasswordEvent;
+pub use gpgme::{PinentryMode, Protocol};
+#[cfg(feature = "eff-wordlist")]
+pub use pass::generate_passphrase;
+#[cfg(feature = "autotype")]
+pub use pass::{autotype, Typer};
 pub use pass::{
-    copy_id, copy_login, copy_otp, copy_password, decrypt_password_file, generate_otp,
-    PasswordInfo, PasswordStore,
+    copy_field, copy_id, copy_login, copy_otp, copy_password, decrypt_binary_file,
+    decrypt_first_lines, decrypt_password_file, decrypt_password_file_with_timeout,
+    decrypt_with_info, fuzzy_match, generate_key, generate_otp, generate_passphrase_from,
+    generate_password, insert_binary_entry, insert_entry, recipients_of, set_gpg_options,
+    set_passphrase_provider, AgeCommand, Backend, CachedEntry, CancellationHandle, CryptoBackend,
+    DecryptionInfo, Decryptor, DirItem, DisplayMetadata, DuplicateCluster, DuplicateKind,
+    EntryBuilder, EntryTemplate, GenerateOptions, GpgOptions, GrepMatch, IdIndex, KeyId, KeyInfo,
+    KeyStatus, PassId, PassphraseProvider, PasswordInfo, PasswordPolicy, PasswordStore,
+    PasswordStoreBuilder, QrCode, RecentAccess, RecentEntry, RecipientDrift, RecipientKey,
+    ScoredMatch, SearchSession, SecretString, SortOrder, StoreChanges, StoreDiff, StoreLock,
+    StoreSnapshot, StoreTree, SymlinkPolicy, TrashEntry, TreeItem, Verification,
 };
 
 #[cfg(test)]
@@ -1,10 +1,86 @@
+mod age;
+mod agent;
+mod analysis;
+mod atomic;
+#[cfg(feature = "autotype")]
+mod autotype;
+mod builder;
+mod cache;
 mod cryptography;
+mod diceware;
+mod fields;
+mod generate;
+mod git;
+mod grep;
+mod history;
+mod ignore;
+mod index;
+mod login_flow;
+mod metadata;
+mod mounts;
 mod operations;
+mod packets;
+mod pass_id;
+mod passphrase;
 mod password_info;
 mod password_store;
+mod permissions;
+mod policy;
+mod qr;
+mod random;
+mod recipients;
+mod reencrypt;
+mod scanner;
+mod search;
+mod sort;
+mod store_lock;
+mod templates;
+#[cfg(test)]
+mod test_dir;
+mod timeout;
+mod trash;
+mod tree;
+mod usage;
+mod walk;
 
+pub use age::AgeCommand;
+pub use analysis::{DuplicateCluster, DuplicateKind};
+#[cfg(feature = "autotype")]
+pub use autotype::{autotype, Typer};
+pub use builder::PasswordStoreBuilder;
+pub use cache::CachedEntry;
+pub use cryptography::{
+    generate_key, set_gpg_options, Backend, CryptoBackend, DecryptionInfo, Decryptor, GpgOptions,
+    Verification,
+};
+#[cfg(feature = "eff-wordlist")]
+pub use diceware::generate_passphrase;
+pub use diceware::generate_passphrase_from;
+pub use fields::EntryBuilder;
+pub use generate::{generate_password, GenerateOptions};
+pub use grep::GrepMatch;
+pub use index::IdIndex;
+pub use metadata::DisplayMetadata;
 pub use operations::{
-    copy_id, copy_login, copy_otp, copy_password, decrypt_password_file, generate_otp,
+    copy_field, copy_id, copy_login, copy_otp, copy_password, decrypt_binary_file,
+    decrypt_first_lines, decrypt_password_file, decrypt_password_file_with_timeout,
+    decrypt_with_info, generate_otp, insert_binary_entry, insert_entry,
 };
+pub use packets::{recipients_of, KeyId};
+pub use pass_id::PassId;
+pub use passphrase::{set_passphrase_provider, KeyInfo, PassphraseProvider, SecretString};
 pub use password_info::PasswordInfo;
-pub use password_store::PasswordStore;
+pub use password_store::{PasswordStore, RecentEntry, StoreChanges, StoreDiff, StoreSnapshot};
+pub use policy::PasswordPolicy;
+pub use qr::QrCode;
+pub use recipients::{KeyStatus, RecipientKey};
+pub use reencrypt::RecipientDrift;
+pub use scanner::SymlinkPolicy;
+pub use search::{fuzzy_match, ScoredMatch, SearchSession};
+pub use sort::SortOrder;
+pub use store_lock::StoreLock;
+pub use templates::EntryTemplate;
+pub use timeout::CancellationHandle;
+pub use trash::TrashEntry;
+pub use tree::{DirItem, StoreTree, TreeItem};
+pub use usage::RecentAccess;
@@ -0,0 +1,78 @@
+use std::{
+    env,
+    io::Write,
+    path::PathBuf,
+    process::{Command, Stdio},
+    thread,
+};
+
+use super::Decryptor;
+use crate::Error;
+
+/// Decrypts age entries, like those of passage, with the `age` command.
+///
+/// Identities of plugins like `age-plugin-yubikey` or `age-plugin-tpm` are handled
+/// by `age` itself, which runs the plugin binary found in `PATH`, so that stores
+/// with hardware-backed identities can be decrypted as well. Register it with
+/// [`PasswordStore::register_decryptor`](crate::PasswordStore::register_decryptor)
+/// for the `age` extension.
+#[derive(Debug, Clone)]
+pub struct AgeCommand {
+    identities: PathBuf,
+}
+
+impl AgeCommand {
+    /// Uses the identities file of passage, `PASSAGE_IDENTITIES_FILE` or
+    /// `~/.passage/identities`.
+    pub fn new() -> Self {
+        let identities = env::var_os("PASSAGE_IDENTITIES_FILE")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| {
+                dirs::home_dir()
+                    .unwrap_or_default()
+                    .join(".passage/identities")
+            });
+        Self::with_identities(identities)
+    }
+
+    /// Uses the identities in the file, which may include plugin identities.
+    pub fn with_identities(identities: impl Into<PathBuf>) -> Self {
+        Self {
+            identities: identities.into(),
+        }
+    }
+}
+
+impl Default for AgeCommand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decryptor for AgeCommand {
+    fn decrypt(&self, cipher: &[u8]) -> Result<Vec<u8>, Error> {
+        let mut child = Command::new("age")
+            .arg("--decrypt")
+            .arg("--identity")
+            .arg(&self.identities)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let mut stdin = child.stdin.take().expect("stdin should be piped");
+        let cipher = cipher.to_vec();
+        // Written from another thread, so that a full stdout pipe cannot block age
+        let writer = thread::spawn(move || stdin.write_all(&cipher));
+        let output = child.wait_with_output()?;
+        writer
+            .join()
+            .map_err(|_| Error::Pass("writing to age panicked".to_string()))??;
+        if !output.status.success() {
+            return Err(Error::Pass(format!(
+                "age failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(output.stdout)
+    }
+}
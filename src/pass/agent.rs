@@ -0,0 +1,104 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use super::{cryptography, PasswordStore, SecretString};
+use crate::Error;
+
+/// Sends commands to the gpg-agent with `gpg-connect-agent` and returns the output.
+fn transact(commands: &[String]) -> Result<String, Error> {
+    let mut command = Command::new("gpg-connect-agent");
+    if let Some(homedir) = cryptography::current_options().homedir {
+        command.arg("--homedir").arg(homedir);
+    }
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    {
+        // Commands are passed on stdin, so that passphrases do not show up in `ps`
+        let mut stdin = child.stdin.take().expect("stdin should be piped");
+        for line in commands {
+            writeln!(stdin, "{line}")?;
+        }
+        writeln!(stdin, "/bye")?;
+    }
+    let output = child.wait_with_output()?;
+    let stdout = String::from_utf8(output.stdout)?;
+    if !output.status.success() {
+        return Err(Error::Pass(format!(
+            "gpg-connect-agent failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    if let Some(error) = stdout.lines().find(|line| line.starts_with("ERR")) {
+        return Err(Error::Pass(format!("gpg-agent error: {error}")));
+    }
+    Ok(stdout)
+}
+
+impl PasswordStore {
+    /// Checks whether the gpg-agent has cached the passphrases of all keys in the
+    /// `.gpg-id` file of the store directory, so that entries can be decrypted
+    /// without a pinentry.
+    pub fn is_unlocked(&self) -> Result<bool, Error> {
+        let keygrips = self.agent_keygrips()?;
+        if keygrips.is_empty() {
+            return Ok(false);
+        }
+        let commands: Vec<String> = keygrips
+            .iter()
+            .map(|keygrip| format!("KEYINFO {keygrip}"))
+            .collect();
+        let output = transact(&commands)?;
+        // S KEYINFO <keygrip> <type> <serialno> <idstr> <cached> <protection> ...
+        let cached = output
+            .lines()
+            .filter(|line| line.starts_with("S KEYINFO "))
+            .filter(|line| line.split_whitespace().nth(6) == Some("1"))
+            .count();
+        Ok(cached == keygrips.len())
+    }
+
+    /// Stores the passphrase of the keys of the store in the gpg-agent cache.
+    ///
+    /// This requires `allow-preset-passphrase` in `gpg-agent.conf`.
+    pub fn preset_passphrase(&self, passphrase: &SecretString) -> Result<(), Error> {
+        let hex: String = passphrase
+            .expose_secret()
+            .bytes()
+            .map(|byte| format!("{byte:02X}"))
+            .collect();
+        let commands: Vec<String> = self
+            .agent_keygrips()?
+            .iter()
+            .map(|keygrip| format!("PRESET_PASSPHRASE {keygrip} -1 {hex}"))
+            .collect();
+        transact(&commands).map(|_| ())
+    }
+
+    /// Removes the passphrases of the keys of the store from the gpg-agent cache.
+    ///
+    /// [`lock`](Self::lock) calls this as well, but ignores errors.
+    pub fn clear_agent_cache(&self) -> Result<(), Error> {
+        let commands: Vec<String> = self
+            .agent_keygrips()?
+            .iter()
+            .map(|keygrip| format!("CLEAR_PASSPHRASE --mode=normal {keygrip}"))
+            .collect();
+        transact(&commands).map(|_| ())
+    }
+
+    /// Returns the keygrips of the secret encryption keys of the store, which the
+    /// gpg-agent uses to identify cached passphrases.
+    fn agent_keygrips(&self) -> Result<Vec<String>, Error> {
+        if !self.uses_gpgme() {
+            return Err(Error::Pass(
+                "the gpg-agent is only used by the gpgme backend".to_string(),
+            ));
+        }
+        cryptography::secret_keygrips(&self.root_gpg_ids()?)
+    }
+}
@@ -0,0 +1,94 @@
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+
+use super::PasswordStore;
+
+/// Describes what the entries of a [`DuplicateCluster`] have in common.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateKind {
+    /// The decrypted contents are identical.
+    Identical,
+    /// Password and login (first two lines) are identical, other lines differ.
+    SameCredentials,
+}
+
+/// A group of entries that are candidates for consolidation into aliases.
+#[derive(Debug, Clone)]
+pub struct DuplicateCluster {
+    pub kind: DuplicateKind,
+    pub ids: Vec<String>,
+}
+
+impl PasswordStore {
+    /// Finds entries that decrypt to identical contents or share the same credentials.
+    ///
+    /// Entries that are already aliases of each other are reported only once, entries
+    /// that can not be decrypted are skipped. Only digests of the decrypted contents
+    /// are kept in memory during the analysis.
+    ///
+    /// This operation is synchronous and will block until all entries are decrypted.
+    pub fn find_duplicates(&self) -> Vec<DuplicateCluster> {
+        self.touch();
+        let mut seen_targets = HashSet::new();
+        let mut by_contents: HashMap<Vec<u8>, Vec<String>> = HashMap::new();
+        let mut by_credentials: HashMap<Vec<u8>, Vec<String>> = HashMap::new();
+
+        for info in self.snapshot().iter() {
+            let path = self.entry_path(&info.id);
+
+            // Skip entries that point to an already visited file
+            if let Ok(target) = path.canonicalize() {
+                if !seen_targets.insert(target) {
+                    continue;
+                }
+            }
+
+            let Ok(file_contents) = self.decrypt_file(&path) else {
+                continue;
+            };
+            let mut lines = file_contents.lines();
+            let password = lines.next().unwrap_or_default();
+            let login = lines.next().unwrap_or_default();
+
+            by_contents
+                .entry(Sha256::digest(file_contents.as_bytes()).to_vec())
+                .or_default()
+                .push(info.id.clone());
+            if !password.is_empty() {
+                let credentials = Sha256::new()
+                    .chain_update(password)
+                    .chain_update([0])
+                    .chain_update(login)
+                    .finalize();
+                by_credentials
+                    .entry(credentials.to_vec())
+                    .or_default()
+                    .push(info.id.clone());
+            }
+        }
+
+        let mut clusters: Vec<DuplicateCluster> = by_contents
+            .into_values()
+            .filter(|ids| ids.len() > 1)
+            .map(|ids| DuplicateCluster {
+                kind: DuplicateKind::Identical,
+                ids,
+            })
+            .collect();
+
+        // Only report credential matches that are not fully covered by identical entries
+        let credential_clusters: Vec<DuplicateCluster> = by_credentials
+            .into_values()
+            .filter(|ids| ids.len() > 1)
+            .filter(|ids| !clusters.iter().any(|cluster| &cluster.ids == ids))
+            .map(|ids| DuplicateCluster {
+                kind: DuplicateKind::SameCredentials,
+                ids,
+            })
+            .collect();
+        clusters.extend(credential_clusters);
+
+        clusters.sort_by(|a, b| a.ids.cmp(&b.ids));
+        clusters
+    }
+}
@@ -0,0 +1,102 @@
+use std::{
+    ffi::OsString,
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use super::permissions;
+
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Writes a file atomically, so that a crash never leaves it truncated.
+///
+/// The contents are written to a temporary file in the same directory, synced
+/// to disk and then renamed to the destination, replacing an existing file.
+/// Missing parent directories are created. The umask from `PASSWORD_STORE_UMASK`
+/// is applied to new files and directories.
+pub fn write(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let temp_path = write_temp(path, contents)?;
+    if let Err(e) = fs::rename(&temp_path, path) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e);
+    }
+    sync_parent(path)
+}
+
+/// Writes a new file atomically like [`write`], failing if the file already exists.
+pub fn write_new(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let temp_path = write_temp(path, contents)?;
+    // Linking fails if the destination exists, unlike renaming
+    let result = fs::hard_link(&temp_path, path);
+    let _ = fs::remove_file(&temp_path);
+    result?;
+    sync_parent(path)
+}
+
+fn write_temp(path: &Path, contents: &[u8]) -> io::Result<PathBuf> {
+    let parent = path
+        .parent()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no parent"))?;
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+    permissions::create_dir_all(parent)?;
+
+    let mut temp_name = OsString::from(".");
+    temp_name.push(file_name);
+    temp_name.push(format!(
+        ".{}.{}.tmp",
+        process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let temp_path = parent.join(temp_name);
+
+    let result = permissions::apply_file_mode(File::options().write(true).create_new(true))
+        .open(&temp_path)
+        .and_then(|mut file| {
+            file.write_all(contents)?;
+            file.sync_all()
+        });
+    match result {
+        Ok(()) => Ok(temp_path),
+        Err(e) => {
+            let _ = fs::remove_file(&temp_path);
+            Err(e)
+        }
+    }
+}
+
+/// Syncs the directory entry of a renamed file.
+#[cfg(unix)]
+fn sync_parent(path: &Path) -> io::Result<()> {
+    match path.parent() {
+        Some(parent) => File::open(parent)?.sync_all(),
+        None => Ok(()),
+    }
+}
+
+#[cfg(not(unix))]
+fn sync_parent(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pass::test_dir::TestDir;
+
+    #[test]
+    fn write_new_does_not_overwrite() {
+        let dir = TestDir::new("atomic");
+        let path = dir.join("nested/entry.gpg");
+
+        write_new(&path, b"first").unwrap();
+        assert!(write_new(&path, b"second").is_err());
+        write(&path, b"third").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"third");
+        assert_eq!(fs::read_dir(path.parent().unwrap()).unwrap().count(), 1);
+    }
+}
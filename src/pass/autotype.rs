@@ -0,0 +1,166 @@
+use std::{
+    env,
+    io::Write,
+    path::Path,
+    process::{Command, Stdio},
+    thread,
+    time::Duration,
+};
+
+use super::{decrypt_password_file, fields, operations::current_otp};
+use crate::Error;
+
+/// Sequence typed for entries without an `autotype:` field.
+const DEFAULT_SEQUENCE: &str = "login :tab pass :enter";
+/// Pause for `:delay`, e.g. while a website loads the password field.
+const DELAY: Duration = Duration::from_millis(500);
+
+/// A step of an autotype sequence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Step {
+    Text(String),
+    Tab,
+    Enter,
+    Delay,
+}
+
+/// Program that sends key events to the focused window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Typer {
+    /// X11
+    Xdotool,
+    /// Wayland compositors supporting the virtual keyboard protocol, like Sway
+    Wtype,
+    /// Any display server through uinput, requires the `ydotoold` daemon
+    Ydotool,
+}
+
+impl Typer {
+    /// Uses `wtype` on Wayland and `xdotool` otherwise.
+    pub fn detect() -> Self {
+        if env::var_os("WAYLAND_DISPLAY").is_some() {
+            Self::Wtype
+        } else {
+            Self::Xdotool
+        }
+    }
+
+    fn type_text(self, text: &str) -> Result<(), Error> {
+        // The text is passed on stdin, so that secrets do not show up in `ps`
+        let (program, args): (_, &[&str]) = match self {
+            Self::Xdotool => ("xdotool", &["type", "--clearmodifiers", "--file", "-"]),
+            Self::Wtype => ("wtype", &["-"]),
+            Self::Ydotool => ("ydotool", &["type", "--file", "-"]),
+        };
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| Error::Pass(format!("autotype requires {program}: {e}")))?;
+        child
+            .stdin
+            .take()
+            .expect("stdin should be piped")
+            .write_all(text.as_bytes())?;
+        Self::check(program, child.wait()?.success())
+    }
+
+    fn press(self, step: &Step) -> Result<(), Error> {
+        let enter = *step == Step::Enter;
+        let (program, args): (_, &[&str]) = match self {
+            Self::Xdotool => ("xdotool", &["key", if enter { "Return" } else { "Tab" }]),
+            Self::Wtype => ("wtype", &["-k", if enter { "Return" } else { "Tab" }]),
+            // Linux input event codes of the keys, pressed and released
+            Self::Ydotool if enter => ("ydotool", &["key", "28:1", "28:0"]),
+            Self::Ydotool => ("ydotool", &["key", "15:1", "15:0"]),
+        };
+        let status = Command::new(program)
+            .args(args)
+            .status()
+            .map_err(|e| Error::Pass(format!("autotype requires {program}: {e}")))?;
+        Self::check(program, status.success())
+    }
+
+    fn check(program: &str, success: bool) -> Result<(), Error> {
+        if success {
+            Ok(())
+        } else {
+            Err(Error::Pass(format!("{program} failed")))
+        }
+    }
+}
+
+/// Types the login, password or other fields of an entry into the focused window,
+/// as an alternative to the clipboard.
+///
+/// The `autotype:` field of the entry defines what is typed, e.g.
+/// `autotype: login :tab pass :enter`, which is also the default. `pass`, `login`
+/// and `otp` stand for the password, the login on the second line and the current
+/// one-time password, other words for the values of fields with that key.
+/// `:tab`, `:enter` and `:delay` press the keys or pause for half a second.
+///
+/// This operation is synchronous and will block until decryption and typing
+/// complete.
+pub fn autotype(file_path: &Path, typer: Typer) -> Result<(), Error> {
+    let contents = decrypt_password_file(file_path)?;
+    let sequence = fields::find_field(&contents, "autotype").unwrap_or(DEFAULT_SEQUENCE);
+    for step in resolve(&contents, sequence)? {
+        match step {
+            Step::Text(text) => typer.type_text(&text)?,
+            Step::Delay => thread::sleep(DELAY),
+            key => typer.press(&key)?,
+        }
+    }
+    Ok(())
+}
+
+/// Replaces the words of the sequence with the values from the entry.
+fn resolve(contents: &str, sequence: &str) -> Result<Vec<Step>, Error> {
+    let mut lines = contents.lines();
+    let password = lines.next().unwrap_or_default();
+    let login = lines.next();
+    sequence
+        .split_whitespace()
+        .map(|word| {
+            Ok(match word {
+                ":tab" => Step::Tab,
+                ":enter" => Step::Enter,
+                ":delay" => Step::Delay,
+                "pass" | "password" => Step::Text(password.to_string()),
+                "otp" => Step::Text(current_otp(contents)?),
+                key => {
+                    let value = fields::find_field(contents, key)
+                        .or(login.filter(|_| key == "login" || key == "user"))
+                        .ok_or_else(|| Error::Pass(format!("no field {key} found")))?;
+                    Step::Text(value.to_string())
+                }
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_sequence() {
+        let contents = "secret\nalice\npin: 1234\n";
+        assert_eq!(
+            resolve(contents, DEFAULT_SEQUENCE).unwrap(),
+            [
+                Step::Text("alice".to_string()),
+                Step::Tab,
+                Step::Text("secret".to_string()),
+                Step::Enter
+            ]
+        );
+        assert_eq!(
+            resolve(contents, "pin :delay").unwrap(),
+            [Step::Text("1234".to_string()), Step::Delay]
+        );
+        assert!(resolve(contents, "email").is_err());
+    }
+}
@@ -0,0 +1,126 @@
+use std::{path::PathBuf, sync::mpsc::Sender};
+
+use super::{PasswordStore, SortOrder, SymlinkPolicy};
+use crate::{Error, PasswordEvent};
+
+/// Options for opening a [`PasswordStore`], see [`PasswordStore::builder`].
+#[derive(Debug, Default)]
+pub struct PasswordStoreBuilder {
+    store_dir: Option<PathBuf>,
+    extensions: Vec<String>,
+    event_tx: Option<Sender<PasswordEvent>>,
+    stores: Vec<(String, PathBuf)>,
+    symlinks: SymlinkPolicy,
+    excludes: Vec<String>,
+    sort_order: SortOrder,
+    prefix: String,
+    max_depth: Option<usize>,
+}
+
+impl PasswordStoreBuilder {
+    /// Sets the store directory, `PASSWORD_STORE_DIR` or `~/.password-store` by
+    /// default.
+    pub fn store_dir(mut self, store_dir: impl Into<PathBuf>) -> Self {
+        self.store_dir = Some(store_dir.into());
+        self
+    }
+
+    /// Sets the file extensions of entries, see [`PasswordStore::set_extensions`].
+    pub fn extensions<I, S>(mut self, extensions: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.extensions = extensions.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Adds another store whose entries are shown under the name, see
+    /// [`PasswordStore::add_store`].
+    pub fn add_store(mut self, name: impl Into<String>, store_dir: impl Into<PathBuf>) -> Self {
+        self.stores.push((name.into(), store_dir.into()));
+        self
+    }
+
+    /// Sets how directories that are symbolic links are scanned, see
+    /// [`SymlinkPolicy`].
+    pub fn symlink_policy(mut self, symlinks: SymlinkPolicy) -> Self {
+        self.symlinks = symlinks;
+        self
+    }
+
+    /// Hides entries and folders matching the pattern, see
+    /// [`PasswordStore::set_exclude_patterns`].
+    pub fn exclude(mut self, pattern: impl Into<String>) -> Self {
+        self.excludes.push(pattern.into());
+        self
+    }
+
+    /// Only reads the entries in the folder, like `work`, and its subfolders.
+    ///
+    /// The IDs of the entries stay relative to the store. The folder may be inside
+    /// of an [added store](Self::add_store).
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into().trim_matches('/').to_string();
+        self
+    }
+
+    /// Only reads entries nested in at most `max_depth` folders below their store,
+    /// `0` only reads the entries at the top level.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Sets the order of [`PasswordStore::sorted_entries`].
+    pub fn sort_order(mut self, order: SortOrder) -> Self {
+        self.sort_order = order;
+        self
+    }
+
+    /// Sets the channel used for sending events, see
+    /// [`PasswordStore::set_event_sender`].
+    pub fn event_sender(mut self, event_tx: Sender<PasswordEvent>) -> Self {
+        self.event_tx = Some(event_tx);
+        self
+    }
+
+    /// Opens the store and reads its entries.
+    ///
+    /// Fails if no store directory was set and the default cannot be determined, see
+    /// [`PasswordStore::get_store_dir`].
+    pub fn build(self) -> Result<PasswordStore, Error> {
+        let store_dir = match self.store_dir {
+            Some(store_dir) => store_dir,
+            None => PasswordStore::get_store_dir()?,
+        };
+
+        let mut store = PasswordStore::default();
+        store.store_dir = store_dir;
+        if let Some(event_tx) = self.event_tx {
+            store.set_event_sender(event_tx);
+        }
+        store.symlinks = self.symlinks;
+        store.excludes = self.excludes;
+        store.sort_by(self.sort_order);
+        store.prefix = self.prefix;
+        store.max_depth = self.max_depth;
+        for (name, store_dir) in self.stores {
+            store.mount(&name, store_dir);
+        }
+        // Both scan the stores
+        if self.extensions.is_empty() {
+            store.reload();
+        } else {
+            store.set_extensions(self.extensions);
+        }
+        Ok(store)
+    }
+}
+
+impl PasswordStore {
+    /// Returns a builder for opening a store with other options than [`new`](Self::new).
+    pub fn builder() -> PasswordStoreBuilder {
+        PasswordStoreBuilder::default()
+    }
+}
@@ -0,0 +1,110 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use super::{atomic, PasswordStore};
+use crate::Error;
+
+/// An entry as recorded by [`PasswordStore::write_cache`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedEntry {
+    pub id: String,
+    pub modified: SystemTime,
+    pub size: u64,
+}
+
+impl PasswordStore {
+    /// Returns the entries of the store directory recorded in the cache, sorted by
+    /// ID, or an empty list if there is no cache.
+    ///
+    /// Reading the cache is much faster than scanning huge stores, so that a list
+    /// can be shown immediately while the store is opened in the background.
+    /// Cached entries may be outdated.
+    pub fn read_cache(store_dir: impl AsRef<Path>) -> Vec<CachedEntry> {
+        let Some(contents) =
+            cache_path(store_dir.as_ref()).and_then(|path| fs::read_to_string(path).ok())
+        else {
+            return Vec::new();
+        };
+        contents.lines().filter_map(parse_line).collect()
+    }
+
+    /// Records the IDs, modification times and sizes of the entries in the
+    /// user cache directory, see [`read_cache`](Self::read_cache).
+    ///
+    /// The cache contains no secrets, but the names of all entries.
+    pub fn write_cache(&self) -> Result<(), Error> {
+        let path = cache_path(&self.store_dir)
+            .ok_or_else(|| Error::Pass("could not determine cache directory".to_string()))?;
+        let mut contents = String::new();
+        for info in self.snapshot().iter() {
+            let modified = info
+                .metadata
+                .modified()
+                .ok()
+                .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+                .unwrap_or_default();
+            contents.push_str(&format!(
+                "{}.{:09}\t{}\t{}\n",
+                modified.as_secs(),
+                modified.subsec_nanos(),
+                info.metadata.len(),
+                info.id
+            ));
+        }
+        atomic::write(&path, contents.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// Returns the cache file of the store directory, named after a hash of its path.
+fn cache_path(store_dir: &Path) -> Option<PathBuf> {
+    Some(
+        dirs::cache_dir()?
+            .join("passepartout")
+            .join(store_hash(store_dir)),
+    )
+}
+
+/// Returns a hash of the canonical path of the store directory for naming files
+/// that belong to the store outside of it.
+pub(super) fn store_hash(store_dir: &Path) -> String {
+    let store_dir = fs::canonicalize(store_dir).unwrap_or_else(|_| store_dir.to_path_buf());
+    // FNV-1a, which is stable across Rust versions unlike the standard hasher
+    let hash = store_dir
+        .to_string_lossy()
+        .bytes()
+        .fold(0xcbf29ce484222325u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+        });
+    format!("{hash:016x}")
+}
+
+fn parse_line(line: &str) -> Option<CachedEntry> {
+    let mut parts = line.splitn(3, '\t');
+    let (secs, nanos) = parts.next()?.split_once('.')?;
+    let modified = UNIX_EPOCH + Duration::new(secs.parse().ok()?, nanos.parse().ok()?);
+    let size = parts.next()?.parse().ok()?;
+    let id = parts.next()?.to_string();
+    Some(CachedEntry { id, modified, size })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_cache_line() {
+        assert_eq!(
+            parse_line("1700000000.000000042\t120\tdir/entry with spaces"),
+            Some(CachedEntry {
+                id: "dir/entry with spaces".to_string(),
+                modified: UNIX_EPOCH + Duration::new(1_700_000_000, 42),
+                size: 120,
+            })
+        );
+        assert_eq!(parse_line("garbage"), None);
+    }
+}
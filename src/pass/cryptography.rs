@@ -1,24 +1,738 @@
-use gpgme::{Context, Protocol};
-use std::cell::RefCell;
+use gpgme::{Context, CreateKeyFlags, Data, EncryptFlags, PinentryMode, Protocol};
+use std::{
+    cell::RefCell,
+    env, io,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        RwLock,
+    },
+    time::Duration,
+};
 
+use super::{
+    passphrase::{has_passphrase_provider, take_passphrase_requested, with_passphrase},
+    KeyId, KeyStatus, RecipientKey,
+};
 use crate::Error;
 
 thread_local! {
-    static GPG_CONTEXT: RefCell<Option<Context>> = const {RefCell::new(None)};
+    /// The context of the current thread with the generation of its options
+    static GPG_CONTEXT: RefCell<Option<(u64, Context)>> = const {RefCell::new(None)};
 }
 
-pub fn decrypt(cipher: &[u8]) -> Result<String, Error> {
+/// Options for new GPG contexts, `None` to read them from the environment.
+static GPG_OPTIONS: RwLock<Option<GpgOptions>> = RwLock::new(None);
+/// Incremented whenever the options change or the contexts are reset, so that
+/// contexts are recreated.
+static GPG_OPTIONS_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Configuration of the gpgme contexts, see [`set_gpg_options`].
+///
+/// Unless options are set explicitly, they are read from `PASSWORD_STORE_GPG_OPTS`
+/// with [`GpgOptions::from_env`]. Unset options fall back to the environment, e.g.
+/// `GNUPGHOME`, and the configuration of GnuPG.
+#[derive(Debug, Clone)]
+pub struct GpgOptions {
+    /// Home directory of GnuPG with the keyring, like `--homedir`
+    pub homedir: Option<PathBuf>,
+    pub pinentry_mode: Option<PinentryMode>,
+    /// Defaults to OpenPGP
+    pub protocol: Protocol,
+    /// Create ASCII armored output, like `--armor`
+    pub armor: bool,
+    /// Encrypt for keys without checking the web of trust, like `--trust-model always`,
+    /// off by default
+    pub always_trust: bool,
+}
+
+impl Default for GpgOptions {
+    fn default() -> Self {
+        Self {
+            homedir: None,
+            pinentry_mode: None,
+            protocol: Protocol::OpenPgp,
+            armor: false,
+            always_trust: false,
+        }
+    }
+}
+
+impl GpgOptions {
+    /// Reads the options from `PASSWORD_STORE_GPG_OPTS`, like pass passes them to gpg.
+    ///
+    /// Supported are `--homedir`, `--pinentry-mode`, `--armor`, `--no-armor`,
+    /// `--trust-model` and `--always-trust`, other options are ignored.
+    pub fn from_env() -> Self {
+        Self::parse(&env::var("PASSWORD_STORE_GPG_OPTS").unwrap_or_default())
+    }
+
+    fn parse(gpg_opts: &str) -> Self {
+        let mut options = Self::default();
+        let mut args = gpg_opts.split_whitespace();
+        while let Some(arg) = args.next() {
+            let (name, inline_value) = match arg.split_once('=') {
+                Some((name, value)) => (name, Some(value)),
+                None => (arg, None),
+            };
+            let mut value = || inline_value.or_else(|| args.next());
+            match name {
+                "--homedir" => options.homedir = value().map(PathBuf::from),
+                "--pinentry-mode" => {
+                    options.pinentry_mode = match value() {
+                        Some("default") => Some(PinentryMode::Default),
+                        Some("ask") => Some(PinentryMode::Ask),
+                        Some("cancel") => Some(PinentryMode::Cancel),
+                        Some("error") => Some(PinentryMode::Error),
+                        Some("loopback") => Some(PinentryMode::Loopback),
+                        _ => options.pinentry_mode,
+                    }
+                }
+                "--armor" | "-a" => options.armor = true,
+                "--no-armor" => options.armor = false,
+                "--trust-model" => options.always_trust = value() == Some("always"),
+                "--always-trust" => options.always_trust = true,
+                _ => {}
+            }
+        }
+        options
+    }
+
+    fn create_context(&self) -> Result<Context, Error> {
+        let mut ctx = Context::from_protocol(self.protocol)?;
+        if let Some(ref homedir) = self.homedir {
+            let homedir = homedir.to_str().ok_or_else(|| {
+                Error::Pass("GnuPG home directory is not valid UTF-8".to_string())
+            })?;
+            ctx.set_engine_home_dir(homedir)?;
+        }
+        if let Some(pinentry_mode) = self.pinentry_mode {
+            ctx.set_pinentry_mode(pinentry_mode)?;
+        }
+        ctx.set_armor(self.armor);
+        Ok(ctx)
+    }
+}
+
+/// Sets the options for the gpgme contexts of all threads.
+///
+/// The options apply to the whole process, existing contexts are recreated on
+/// their next use.
+pub fn set_gpg_options(options: GpgOptions) {
+    *GPG_OPTIONS.write().unwrap_or_else(|e| e.into_inner()) = Some(options);
+    GPG_OPTIONS_GENERATION.fetch_add(1, Ordering::SeqCst);
+}
+
+pub(super) fn current_options() -> GpgOptions {
+    GPG_OPTIONS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .unwrap_or_else(GpgOptions::from_env)
+}
+
+/// Runs `f` with the GPG context of the current thread, creating it on first use.
+fn with_context<T>(f: impl FnOnce(&mut Context) -> Result<T, Error>) -> Result<T, Error> {
     GPG_CONTEXT.with(|ctx| {
         let mut ctx_ref = ctx.borrow_mut();
-        if ctx_ref.is_none() {
-            *ctx_ref = Some(Context::from_protocol(Protocol::OpenPgp)?);
+        let generation = GPG_OPTIONS_GENERATION.load(Ordering::SeqCst);
+        if ctx_ref.as_ref().is_none_or(|(g, _)| *g != generation) {
+            let options = current_options();
+            *ctx_ref = Some((generation, options.create_context()?));
+        }
+
+        f(&mut ctx_ref.as_mut().unwrap().1)
+    })
+}
+
+/// Decrypts the cipher, the plaintext must be valid UTF-8.
+pub fn decrypt(cipher: &[u8]) -> Result<String, Error> {
+    String::from_utf8(decrypt_bytes(cipher)?).map_err(|e| e.into())
+}
+
+/// Decrypts the cipher without requiring the plaintext to be valid UTF-8.
+pub fn decrypt_bytes(cipher: &[u8]) -> Result<Vec<u8>, Error> {
+    with_context(|ctx| {
+        with_passphrase(ctx, |ctx| {
+            let mut plain = Vec::new();
+
+            ctx.decrypt(cipher, &mut plain)?;
+
+            Ok(plain)
+        })
+    })
+}
+
+/// Output of a decryption that stops accepting plaintext after a number of lines.
+struct LineLimit {
+    plain: Vec<u8>,
+    remaining: usize,
+}
+
+impl io::Write for LineLimit {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.remaining == 0 {
+            // Makes gpgme abort the decryption
+            return Err(io::Error::other("line limit reached"));
         }
+        let mut end = buf.len();
+        for (i, _) in buf.iter().enumerate().filter(|(_, byte)| **byte == b'\n') {
+            self.remaining -= 1;
+            if self.remaining == 0 {
+                end = i + 1;
+                break;
+            }
+        }
+        self.plain.extend_from_slice(&buf[..end]);
+        Ok(end)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Decrypts the first lines of the cipher, the decryption is stopped once they
+/// have been read.
+///
+/// As the rest of the message is never processed, its integrity is not checked.
+pub fn decrypt_lines(cipher: &[u8], lines: usize) -> Result<String, Error> {
+    with_context(|ctx| {
+        with_passphrase(ctx, |ctx| {
+            let mut output = LineLimit {
+                plain: Vec::new(),
+                remaining: lines,
+            };
+            let result = {
+                let mut data = Data::from_writer(&mut output).map_err(|e| e.error())?;
+                ctx.decrypt(cipher, &mut data)
+            };
+            match result {
+                Ok(_) => {}
+                Err(_) if output.remaining == 0 => {}
+                Err(e) => return Err(e.into()),
+            }
+            Ok(String::from_utf8(std::mem::take(&mut output.plain))?)
+        })
+    })
+}
 
-        let ctx = ctx_ref.as_mut().unwrap();
+/// Details about the decryption of an entry, see [`decrypt_with_info`](crate::decrypt_with_info).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecryptionInfo {
+    /// Key ID of the (sub)key that decrypted the entry
+    pub key_id: Option<String>,
+    /// Key IDs of all recipients the entry is encrypted for
+    pub recipients: Vec<String>,
+    /// Whether the passphrase was cached by the gpg-agent, only known while a
+    /// [passphrase provider](crate::set_passphrase_provider) is set
+    pub cached: Option<bool>,
+}
+
+/// Decrypts the cipher and reports which key was used.
+pub fn decrypt_bytes_with_info(cipher: &[u8]) -> Result<(Vec<u8>, DecryptionInfo), Error> {
+    with_context(|ctx| {
+        let tracked = has_passphrase_provider();
+        take_passphrase_requested();
         let mut plain = Vec::new();
+        let result = with_passphrase(ctx, |ctx| Ok(ctx.decrypt(cipher, &mut plain)?))?;
+        let requested = take_passphrase_requested();
+
+        let key_id =
+            |recipient: &gpgme::results::Recipient<'_>| recipient.key_id().ok().map(String::from);
+        let info = DecryptionInfo {
+            key_id: result
+                .recipients()
+                .find(|recipient| recipient.status().is_ok())
+                .and_then(|recipient| key_id(&recipient)),
+            recipients: result.recipients().filter_map(|r| key_id(&r)).collect(),
+            cached: tracked.then_some(!requested),
+        };
+        Ok((plain, info))
+    })
+}
+
+/// Encrypts the plaintext for the given recipients.
+///
+/// Recipients are resolved like `gpg --recipient` does, using the first key that is
+/// usable for encryption. The keys must be valid in the web of trust unless
+/// [`GpgOptions::always_trust`] is set. Fails without recipients, since gpgme
+/// would fall back to symmetric encryption.
+pub fn encrypt(plain: &[u8], recipients: &[String]) -> Result<Vec<u8>, Error> {
+    with_context(|ctx| {
+        let keys = encryption_keys(ctx, recipients)?;
+        let mut cipher = Vec::new();
+        ctx.encrypt_with_flags(&keys, plain, &mut cipher, encrypt_flags())?;
+        Ok(cipher)
+    })
+}
+
+/// Encrypts the plaintext for the recipients and signs it with the secret keys of
+/// the signers.
+pub fn sign_and_encrypt(
+    plain: &[u8],
+    recipients: &[String],
+    signers: &[String],
+) -> Result<Vec<u8>, Error> {
+    with_context(|ctx| {
+        let keys = encryption_keys(ctx, recipients)?;
+        ctx.clear_signers();
+        let mut cipher = Vec::new();
+        let result = (|| {
+            add_signers(ctx, signers)?;
+            with_passphrase(ctx, |ctx| {
+                Ok(ctx.sign_and_encrypt_with_flags(&keys, plain, &mut cipher, encrypt_flags())?)
+            })?;
+            Ok(())
+        })();
+        ctx.clear_signers();
+        result.map(|_| cipher)
+    })
+}
+
+/// Looks up a key usable for encryption for every recipient.
+fn encryption_keys(ctx: &mut Context, recipients: &[String]) -> Result<Vec<gpgme::Key>, Error> {
+    if recipients.is_empty() {
+        return Err(Error::Pass(
+            "at least one recipient is required".to_string(),
+        ));
+    }
+    let mut keys = Vec::with_capacity(recipients.len());
+    for recipient in recipients {
+        let key = ctx
+            .find_keys([recipient.as_str()])?
+            .filter_map(Result::ok)
+            .find(|key| key.can_encrypt() && !key.is_revoked() && !key.is_expired())
+            .ok_or_else(|| Error::Pass(format!("no usable key found for {recipient}")))?;
+        keys.push(key);
+    }
+    Ok(keys)
+}
+
+fn encrypt_flags() -> EncryptFlags {
+    if current_options().always_trust {
+        EncryptFlags::ALWAYS_TRUST
+    } else {
+        EncryptFlags::empty()
+    }
+}
+
+/// Adds the secret keys of the signers to the context.
+fn add_signers(ctx: &mut Context, signers: &[String]) -> Result<(), Error> {
+    for signer in signers {
+        let key = ctx
+            .find_secret_keys([signer.as_str()])?
+            .filter_map(Result::ok)
+            .find(|key| key.can_sign())
+            .ok_or_else(|| Error::Pass(format!("no signing key found for {signer}")))?;
+        ctx.add_signer(&key)?;
+    }
+    Ok(())
+}
+
+/// Creates a new OpenPGP key for the user ID, e.g. `"Alice <alice@example.org>"`,
+/// and returns its fingerprint.
+///
+/// The key is created with the default algorithms of GnuPG and has a subkey for
+/// encryption, so it can be used as GPG ID of a store right away. GnuPG asks for
+/// the passphrase protecting the key with its pinentry or the
+/// [passphrase provider](crate::set_passphrase_provider). `None` for `expires`
+/// creates a key that does not expire.
+pub fn generate_key(user_id: &str, expires: Option<Duration>) -> Result<String, Error> {
+    with_context(|ctx| {
+        let (expires, flags) = match expires {
+            Some(expires) => (expires, CreateKeyFlags::empty()),
+            None => (Duration::ZERO, CreateKeyFlags::NOEXPIRE),
+        };
+        let result = with_passphrase(ctx, |ctx| {
+            Ok(ctx.create_key_with_flags(user_id, "default", expires, flags)?)
+        })?;
+        result
+            .fingerprint()
+            .map(String::from)
+            .map_err(|_| Error::Pass("no fingerprint for the generated key".to_string()))
+    })
+}
+
+/// Creates a detached signature of the data with the secret keys of the signers.
+pub fn sign_detached(data: &[u8], signers: &[String]) -> Result<Vec<u8>, Error> {
+    with_context(|ctx| {
+        ctx.clear_signers();
+        let mut signature = Vec::new();
+        let result = (|| {
+            add_signers(ctx, signers)?;
+            with_passphrase(ctx, |ctx| Ok(ctx.sign_detached(data, &mut signature)?))?;
+            Ok(())
+        })();
+        ctx.clear_signers();
+        result.map(|_| signature)
+    })
+}
 
-        ctx.decrypt(cipher, &mut plain)?;
+/// Verifies a detached signature of the data.
+///
+/// Returns the fingerprints of the primary keys that made valid signatures.
+pub fn verify_detached(data: &[u8], signature: &[u8]) -> Result<Vec<String>, Error> {
+    with_context(|ctx| {
+        let result = ctx.verify_detached(signature, data)?;
+        Ok(valid_signers(ctx, &result))
+    })
+}
+
+/// Whether the contents of an entry carry a valid signature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verification {
+    /// Signed by the key with this primary key fingerprint
+    ///
+    /// Any recipient of a shared store can sign with their own key, so the
+    /// fingerprint should be compared with the keys that are trusted to write.
+    Verified { fingerprint: String },
+    /// Not signed or the signature is not valid
+    Unverified,
+}
+
+/// Decrypts the cipher and verifies the signature of the plaintext.
+pub fn decrypt_and_verify(cipher: &[u8]) -> Result<(Vec<u8>, Verification), Error> {
+    with_context(|ctx| {
+        with_passphrase(ctx, |ctx| {
+            let mut plain = Vec::new();
+            let (_, result) = ctx.decrypt_and_verify(cipher, &mut plain)?;
+            let verification = match valid_signers(ctx, &result).into_iter().next() {
+                Some(fingerprint) => Verification::Verified { fingerprint },
+                None => Verification::Unverified,
+            };
+            Ok((plain, verification))
+        })
+    })
+}
+
+/// Returns the fingerprints of the primary keys that made valid signatures.
+fn valid_signers(ctx: &mut Context, result: &gpgme::VerificationResult) -> Vec<String> {
+    let signers: Vec<String> = result
+        .signatures()
+        .filter(|signature| signature.status().is_ok())
+        .filter_map(|signature| signature.fingerprint().ok().map(String::from))
+        .collect();
+    // Signatures are usually made by a subkey, compare the primary keys instead
+    signers
+        .into_iter()
+        .map(|fingerprint| {
+            ctx.get_key(fingerprint.as_str())
+                .ok()
+                .and_then(|key| key.fingerprint().ok().map(String::from))
+                .unwrap_or(fingerprint)
+        })
+        .collect()
+}
+
+/// Looks up the keys of the recipients in the keyring and reports their status.
+///
+/// For every recipient, the first key that is usable for encryption is reported,
+/// like [`encrypt`] selects it, or the first matching key otherwise.
+pub fn inspect_keys(recipients: &[String]) -> Result<Vec<RecipientKey>, Error> {
+    with_context(|ctx| {
+        let mut report = Vec::with_capacity(recipients.len());
+        for recipient in recipients {
+            let keys: Vec<_> = ctx
+                .find_keys([recipient.as_str()])?
+                .filter_map(Result::ok)
+                .collect();
+            let status = |key: &gpgme::Key| {
+                if key.is_revoked() {
+                    KeyStatus::Revoked
+                } else if key.is_expired() {
+                    KeyStatus::Expired
+                } else if key.is_disabled() || key.is_invalid() || !key.can_encrypt() {
+                    KeyStatus::Unusable
+                } else {
+                    KeyStatus::Usable
+                }
+            };
+            let key = keys
+                .iter()
+                .find(|key| status(key) == KeyStatus::Usable)
+                .or_else(|| keys.first());
+            report.push(RecipientKey {
+                gpg_id: recipient.clone(),
+                fingerprint: key.and_then(|key| key.fingerprint().ok().map(String::from)),
+                user_id: key.and_then(|key| {
+                    key.user_ids()
+                        .next()
+                        .and_then(|user_id| user_id.id().ok().map(String::from))
+                }),
+                encryption_keys: key
+                    .map(|key| {
+                        key.subkeys()
+                            .filter(|subkey| subkey.can_encrypt())
+                            .filter_map(|subkey| subkey.fingerprint().ok().map(String::from))
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+                status: key.map_or(KeyStatus::Missing, status),
+            });
+        }
+        Ok(report)
+    })
+}
 
-        String::from_utf8(plain).map_err(|e| e.into())
+/// Returns the keygrips of the secret subkeys of the recipients that can encrypt.
+pub fn secret_keygrips(recipients: &[String]) -> Result<Vec<String>, Error> {
+    with_context(|ctx| {
+        let mut keygrips = Vec::new();
+        for recipient in recipients {
+            for key in ctx
+                .find_secret_keys([recipient.as_str()])?
+                .filter_map(Result::ok)
+            {
+                keygrips.extend(
+                    key.subkeys()
+                        .filter(|subkey| subkey.can_encrypt())
+                        .filter_map(|subkey| subkey.keygrip().ok().map(String::from)),
+                );
+            }
+        }
+        keygrips.sort_unstable();
+        keygrips.dedup();
+        Ok(keygrips)
     })
 }
+
+/// Checks whether one of the recipients is a secret key stored on a smartcard.
+pub fn uses_card_key(recipients: &[KeyId]) -> Result<bool, Error> {
+    with_context(|ctx| {
+        for key in ctx.secret_keys()?.filter_map(Result::ok) {
+            let on_card = key.subkeys().any(|subkey| {
+                subkey.is_card_key()
+                    && subkey.fingerprint().is_ok_and(|fingerprint| {
+                        recipients
+                            .iter()
+                            .any(|key_id| key_id.matches_fingerprint(fingerprint))
+                    })
+            });
+            if on_card {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    })
+}
+
+/// An OpenPGP implementation used to decrypt and encrypt entries.
+///
+/// Implementations must be usable from multiple threads, since a store is shared
+/// between UI and worker threads.
+pub trait CryptoBackend: Send + Sync {
+    /// Decrypts the cipher without requiring the plaintext to be valid UTF-8.
+    fn decrypt(&self, cipher: &[u8]) -> Result<Vec<u8>, Error>;
+
+    /// Encrypts the plaintext for the given recipients, which are GPG IDs as listed
+    /// in `.gpg-id` files.
+    fn encrypt(&self, plain: &[u8], recipients: &[String]) -> Result<Vec<u8>, Error>;
+
+    /// Resolves the recipients to keys and reports whether they can be used.
+    ///
+    /// Fails by default.
+    fn inspect_keys(&self, _recipients: &[String]) -> Result<Vec<RecipientKey>, Error> {
+        Err(Error::Pass(
+            "key inspection is not supported by the backend".to_string(),
+        ))
+    }
+
+    /// Checks whether decrypting for one of the recipients involves a hardware token
+    /// like a smartcard or YubiKey.
+    ///
+    /// Returns `false` by default.
+    fn uses_hardware_key(&self, _recipients: &[KeyId]) -> Result<bool, Error> {
+        Ok(false)
+    }
+
+    /// Verifies a detached signature and returns the fingerprints of the primary keys
+    /// that made valid signatures.
+    ///
+    /// Needed for `.gpg-id` files when `PASSWORD_STORE_SIGNING_KEY` is set, fails by
+    /// default.
+    fn verify_detached(&self, _data: &[u8], _signature: &[u8]) -> Result<Vec<String>, Error> {
+        Err(Error::Pass(
+            "signature verification is not supported by the backend".to_string(),
+        ))
+    }
+
+    /// Encrypts the plaintext for the recipients and signs it with the keys of the
+    /// signers, see [`PasswordStore::set_signing_keys`](crate::PasswordStore::set_signing_keys).
+    ///
+    /// Fails by default.
+    fn sign_and_encrypt(
+        &self,
+        _plain: &[u8],
+        _recipients: &[String],
+        _signers: &[String],
+    ) -> Result<Vec<u8>, Error> {
+        Err(Error::Pass(
+            "signing entries is not supported by the backend".to_string(),
+        ))
+    }
+
+    /// Decrypts the cipher and verifies the signature of the plaintext.
+    ///
+    /// Decrypts without verifying by default, so entries are always unverified.
+    fn decrypt_and_verify(&self, cipher: &[u8]) -> Result<(Vec<u8>, Verification), Error> {
+        Ok((self.decrypt(cipher)?, Verification::Unverified))
+    }
+}
+
+/// Decrypts entry files with a custom format, see
+/// [`PasswordStore::register_decryptor`](crate::PasswordStore::register_decryptor).
+///
+/// Unlike a [`CryptoBackend`], a decryptor only handles the files with its
+/// extension, e.g. entries wrapped by a key management service, while all other
+/// entries are still decrypted by the backend of the store.
+pub trait Decryptor: Send + Sync {
+    /// Decrypts the contents of the entry file.
+    fn decrypt(&self, cipher: &[u8]) -> Result<Vec<u8>, Error>;
+}
+
+/// The crypto backend of a store, selected at runtime.
+///
+/// Only gpgme is built in, other implementations like Sequoia can be provided with
+/// [`Backend::Custom`].
+#[derive(Default)]
+pub enum Backend {
+    /// GnuPG through gpgme, using the keyring and agent of the user
+    #[default]
+    Gpgme,
+    Custom(Box<dyn CryptoBackend>),
+}
+
+impl CryptoBackend for Backend {
+    fn decrypt(&self, cipher: &[u8]) -> Result<Vec<u8>, Error> {
+        match self {
+            Self::Gpgme => decrypt_bytes(cipher),
+            Self::Custom(backend) => backend.decrypt(cipher),
+        }
+    }
+
+    fn encrypt(&self, plain: &[u8], recipients: &[String]) -> Result<Vec<u8>, Error> {
+        match self {
+            Self::Gpgme => encrypt(plain, recipients),
+            Self::Custom(backend) => backend.encrypt(plain, recipients),
+        }
+    }
+
+    fn inspect_keys(&self, recipients: &[String]) -> Result<Vec<RecipientKey>, Error> {
+        match self {
+            Self::Gpgme => inspect_keys(recipients),
+            Self::Custom(backend) => backend.inspect_keys(recipients),
+        }
+    }
+
+    fn uses_hardware_key(&self, recipients: &[KeyId]) -> Result<bool, Error> {
+        match self {
+            Self::Gpgme => uses_card_key(recipients),
+            Self::Custom(backend) => backend.uses_hardware_key(recipients),
+        }
+    }
+
+    fn verify_detached(&self, data: &[u8], signature: &[u8]) -> Result<Vec<String>, Error> {
+        match self {
+            Self::Gpgme => verify_detached(data, signature),
+            Self::Custom(backend) => backend.verify_detached(data, signature),
+        }
+    }
+
+    fn sign_and_encrypt(
+        &self,
+        plain: &[u8],
+        recipients: &[String],
+        signers: &[String],
+    ) -> Result<Vec<u8>, Error> {
+        match self {
+            Self::Gpgme => sign_and_encrypt(plain, recipients, signers),
+            Self::Custom(backend) => backend.sign_and_encrypt(plain, recipients, signers),
+        }
+    }
+
+    fn decrypt_and_verify(&self, cipher: &[u8]) -> Result<(Vec<u8>, Verification), Error> {
+        match self {
+            Self::Gpgme => decrypt_and_verify(cipher),
+            Self::Custom(backend) => backend.decrypt_and_verify(cipher),
+        }
+    }
+}
+
+/// A backend that signs everything it encrypts, unless there are no signers.
+pub(super) struct SigningBackend<'a> {
+    pub backend: &'a Backend,
+    pub signers: &'a [String],
+}
+
+impl CryptoBackend for SigningBackend<'_> {
+    fn decrypt(&self, cipher: &[u8]) -> Result<Vec<u8>, Error> {
+        self.backend.decrypt(cipher)
+    }
+
+    fn encrypt(&self, plain: &[u8], recipients: &[String]) -> Result<Vec<u8>, Error> {
+        if self.signers.is_empty() {
+            self.backend.encrypt(plain, recipients)
+        } else {
+            self.backend
+                .sign_and_encrypt(plain, recipients, self.signers)
+        }
+    }
+
+    fn inspect_keys(&self, recipients: &[String]) -> Result<Vec<RecipientKey>, Error> {
+        self.backend.inspect_keys(recipients)
+    }
+
+    fn uses_hardware_key(&self, recipients: &[KeyId]) -> Result<bool, Error> {
+        self.backend.uses_hardware_key(recipients)
+    }
+
+    fn verify_detached(&self, data: &[u8], signature: &[u8]) -> Result<Vec<String>, Error> {
+        self.backend.verify_detached(data, signature)
+    }
+
+    fn decrypt_and_verify(&self, cipher: &[u8]) -> Result<(Vec<u8>, Verification), Error> {
+        self.backend.decrypt_and_verify(cipher)
+    }
+}
+
+/// Drops the GPG contexts, new ones are created on the next use.
+///
+/// The context of the current thread is dropped immediately, those of other
+/// threads, like the decryption worker, before their next use.
+pub fn reset_context() {
+    GPG_CONTEXT.with(|ctx| ctx.borrow_mut().take());
+    GPG_OPTIONS_GENERATION.fetch_add(1, Ordering::SeqCst);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn line_limit_stops_after_lines() {
+        let mut output = LineLimit {
+            plain: Vec::new(),
+            remaining: 2,
+        };
+        assert_eq!(output.write(b"password\nlogin\nnotes").unwrap(), 15);
+        assert!(output.write(b"more").is_err());
+        assert_eq!(output.plain, b"password\nlogin\n");
+    }
+
+    #[test]
+    fn parse_gpg_opts() {
+        let options = GpgOptions::parse("--quiet --homedir /tmp/gnupg --pinentry-mode=loopback -a");
+        assert_eq!(options.homedir, Some(PathBuf::from("/tmp/gnupg")));
+        assert_eq!(options.pinentry_mode, Some(PinentryMode::Loopback));
+        assert!(options.armor);
+        assert!(!options.always_trust);
+
+        let options = GpgOptions::parse("--trust-model pgp");
+        assert!(!options.always_trust);
+        assert_eq!(options.homedir, None);
+        assert!(GpgOptions::parse("--trust-model always").always_trust);
+        assert!(GpgOptions::parse("--always-trust").always_trust);
+    }
+}
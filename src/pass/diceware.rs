@@ -0,0 +1,69 @@
+use super::random;
+use crate::Error;
+
+/// The EFF large wordlist with 7776 words, one per combination of five dice.
+#[cfg(feature = "eff-wordlist")]
+const EFF_LARGE_WORDLIST: &str = include_str!("../../wordlists/eff_large_wordlist.txt");
+
+/// Generates a diceware passphrase from the EFF large wordlist.
+///
+/// Every word adds about 12.9 bits of entropy, six words are recommended.
+#[cfg(feature = "eff-wordlist")]
+pub fn generate_passphrase(words: usize, separator: &str) -> Result<String, Error> {
+    generate_passphrase_from(EFF_LARGE_WORDLIST, words, separator)
+}
+
+/// Generates a diceware passphrase from a wordlist.
+///
+/// The wordlist contains one word per line, optionally preceded by its dice roll
+/// like in the EFF wordlists. Duplicate words are ignored.
+pub fn generate_passphrase_from(
+    wordlist: &str,
+    words: usize,
+    separator: &str,
+) -> Result<String, Error> {
+    if words == 0 {
+        return Err(Error::Pass("number of words must be positive".to_string()));
+    }
+    let mut list: Vec<&str> = wordlist
+        .lines()
+        .filter_map(|line| line.split_whitespace().last())
+        .collect();
+    list.sort_unstable();
+    list.dedup();
+    if list.len() < 2 {
+        return Err(Error::Pass(
+            "wordlist must contain at least two words".to_string(),
+        ));
+    }
+
+    let passphrase = (0..words)
+        .map(|_| random::below(list.len() as u32).map(|i| list[i as usize]))
+        .collect::<Result<Vec<_>, Error>>()?;
+    Ok(passphrase.join(separator))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn passphrase_uses_wordlist() {
+        let wordlist = "11111\tabacus\n11112\tabdomen\n\n11113\tabdominal\n";
+        let passphrase = generate_passphrase_from(wordlist, 5, "-").unwrap();
+        let words: Vec<&str> = passphrase.split('-').collect();
+        assert_eq!(words.len(), 5);
+        assert!(words
+            .iter()
+            .all(|word| ["abacus", "abdomen", "abdominal"].contains(word)));
+        assert!(generate_passphrase_from("single", 5, " ").is_err());
+    }
+
+    #[cfg(feature = "eff-wordlist")]
+    #[test]
+    fn eff_passphrase() {
+        assert_eq!(EFF_LARGE_WORDLIST.lines().count(), 7776);
+        let passphrase = generate_passphrase(6, " ").unwrap();
+        assert_eq!(passphrase.split(' ').count(), 6);
+    }
+}
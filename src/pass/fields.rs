@@ -0,0 +1,137 @@
+/// Assembles the contents of a multiline entry.
+///
+/// The password is placed on the first line and the login on the second line, where
+/// [`copy_password`](crate::copy_password) and [`copy_login`](crate::copy_login)
+/// expect them, followed by the fields, the OTP URI and the notes.
+#[derive(Debug, Clone, Default)]
+pub struct EntryBuilder {
+    password: String,
+    login: Option<String>,
+    fields: Vec<(String, String)>,
+    otpauth: Option<String>,
+    notes: Option<String>,
+}
+
+impl EntryBuilder {
+    pub fn new(password: impl Into<String>) -> Self {
+        Self {
+            password: password.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn login(mut self, login: impl Into<String>) -> Self {
+        self.login = Some(login.into());
+        self
+    }
+
+    /// Adds a `key: value` line.
+    pub fn field(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.fields.push((key.into(), value.into()));
+        self
+    }
+
+    pub fn otpauth(mut self, otpauth: impl Into<String>) -> Self {
+        self.otpauth = Some(otpauth.into());
+        self
+    }
+
+    /// Adds free-form notes, which may span multiple lines.
+    pub fn notes(mut self, notes: impl Into<String>) -> Self {
+        self.notes = Some(notes.into());
+        self
+    }
+
+    /// Returns the contents of the entry.
+    pub fn build(&self) -> String {
+        let mut lines = vec![self.password.clone()];
+        lines.extend(self.login.clone());
+        lines.extend(
+            self.fields
+                .iter()
+                .map(|(key, value)| format!("{key}: {value}")),
+        );
+        lines.extend(self.otpauth.clone());
+        lines.extend(
+            self.notes
+                .as_deref()
+                .map(|notes| notes.trim_end().to_string()),
+        );
+
+        let mut contents = lines.join("\n");
+        contents.push('\n');
+        contents
+    }
+}
+
+/// Replaces the value of the first field with the key, or appends a new field.
+///
+/// The password on the first line and the order of all other lines are preserved.
+pub fn set_field(contents: &str, key: &str, value: &str) -> String {
+    let mut lines: Vec<String> = contents.lines().map(String::from).collect();
+    if lines.is_empty() {
+        // Keep the first line reserved for the password
+        lines.push(String::new());
+    }
+
+    let field = format!("{key}: {value}");
+    match lines
+        .iter()
+        .skip(1)
+        .position(|line| split_field(line).is_some_and(|(k, _)| k.eq_ignore_ascii_case(key)))
+    {
+        Some(i) => lines[i + 1] = field,
+        None => lines.push(field),
+    }
+
+    let mut result = lines.join("\n");
+    result.push('\n');
+    result
+}
+
+/// Returns the value of the first field with the key, ignoring case.
+///
+/// The first line is skipped, since it holds the password.
+pub fn find_field<'a>(contents: &'a str, key: &str) -> Option<&'a str> {
+    contents
+        .lines()
+        .skip(1)
+        .filter_map(split_field)
+        .find(|(k, _)| k.eq_ignore_ascii_case(key))
+        .map(|(_, value)| value)
+}
+
+fn split_field(line: &str) -> Option<(&str, &str)> {
+    let (key, value) = line.split_once(':')?;
+    let key = key.trim();
+    // URIs like otpauth:// are not fields
+    if key.is_empty() || key.contains(char::is_whitespace) || value.starts_with("//") {
+        return None;
+    }
+    Some((key, value.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_field_ignores_case() {
+        let contents = "pin: 1234\nPIN: 5678\notpauth://totp/x?secret=A\n";
+        assert_eq!(find_field(contents, "Pin"), Some("5678"));
+        assert_eq!(find_field(contents, "otpauth"), None);
+    }
+
+    #[test]
+    fn set_field_preserves_lines() {
+        let contents = "secret\nlogin: alice\nnotes\n";
+        assert_eq!(
+            set_field(contents, "Login", "bob"),
+            "secret\nLogin: bob\nnotes\n"
+        );
+        assert_eq!(
+            set_field(contents, "url", "https://example.org"),
+            "secret\nlogin: alice\nnotes\nurl: https://example.org\n"
+        );
+    }
+}
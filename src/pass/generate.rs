@@ -0,0 +1,220 @@
+use std::env;
+
+use super::{operations, random, PasswordStore};
+use crate::{
+    clipboard::{copy_to_clipboard, ClipboardOptions, Content},
+    Error,
+};
+
+const DEFAULT_LENGTH: usize = 25;
+const ALNUM: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+const PUNCT: &str = "!\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~";
+const CONSONANTS: [&str; 24] = [
+    "b", "c", "d", "f", "g", "h", "j", "k", "l", "m", "n", "p", "r", "s", "t", "v", "w", "x", "z",
+    "ch", "sh", "th", "st", "tr",
+];
+const VOWELS: [&str; 8] = ["a", "e", "i", "o", "u", "ai", "ee", "oo"];
+
+/// Options for password generation, mirroring the flags of `pass generate`.
+#[derive(Debug, Clone)]
+pub struct GenerateOptions {
+    /// Number of characters, defaults to `PASSWORD_STORE_GENERATED_LENGTH` or 25
+    pub length: usize,
+    /// Use only alphanumeric characters (`-n`)
+    pub no_symbols: bool,
+    /// Copy the password to the clipboard instead of only returning it (`-c`), it is
+    /// cleared like with the default [`ClipboardOptions`]
+    pub clip: bool,
+    /// Replace only the first line of an existing entry (`-i`)
+    pub in_place: bool,
+    /// Overwrite an existing entry (`-f`)
+    pub force: bool,
+    /// Characters to choose from, overriding the character set variables of pass
+    pub character_set: Option<String>,
+    /// Alternate lowercase consonants and vowels instead of using the character set,
+    /// like `pwgen -A`
+    pub pronounceable: bool,
+}
+
+impl Default for GenerateOptions {
+    fn default() -> Self {
+        let length = env::var("PASSWORD_STORE_GENERATED_LENGTH")
+            .ok()
+            .and_then(|length| length.parse().ok())
+            .filter(|length| *length > 0)
+            .unwrap_or(DEFAULT_LENGTH);
+        Self {
+            length,
+            no_symbols: false,
+            clip: false,
+            in_place: false,
+            force: false,
+            character_set: None,
+            pronounceable: false,
+        }
+    }
+}
+
+impl GenerateOptions {
+    /// Returns the characters to choose from.
+    ///
+    /// Unless set explicitly, this honors `PASSWORD_STORE_CHARACTER_SET` and
+    /// `PASSWORD_STORE_CHARACTER_SET_NO_SYMBOLS` like pass does, when they only
+    /// contain literal characters.
+    fn character_set(&self) -> Vec<char> {
+        let set = self.character_set.clone().unwrap_or_else(|| {
+            let (variable, default) = if self.no_symbols {
+                ("PASSWORD_STORE_CHARACTER_SET_NO_SYMBOLS", ALNUM.to_string())
+            } else {
+                ("PASSWORD_STORE_CHARACTER_SET", format!("{ALNUM}{PUNCT}"))
+            };
+            env::var(variable)
+                .ok()
+                .filter(|set| !set.is_empty() && !set.contains("[:"))
+                .unwrap_or(default)
+        });
+
+        let mut characters: Vec<char> = set.chars().collect();
+        characters.sort_unstable();
+        characters.dedup();
+        characters
+    }
+}
+
+/// Generates a random password from the character set of the options.
+pub fn generate_password(options: &GenerateOptions) -> Result<String, Error> {
+    if options.length == 0 {
+        return Err(Error::Pass("password length must be positive".to_string()));
+    }
+    if options.pronounceable {
+        return generate_pronounceable(options.length);
+    }
+    let characters = options.character_set();
+    (0..options.length)
+        .map(|_| random::below(characters.len() as u32).map(|i| characters[i as usize]))
+        .collect()
+}
+
+/// Generates a password from alternating consonant and vowel sounds.
+///
+/// Pronounceable passwords are easier to type and remember but have less entropy
+/// per character, so they should be longer than random ones.
+fn generate_pronounceable(length: usize) -> Result<String, Error> {
+    let mut password = String::with_capacity(length + 1);
+    let mut vowel = random::below(2)? == 0;
+    while password.len() < length {
+        let sounds: &[&str] = if vowel { &VOWELS } else { &CONSONANTS };
+        let sound = sounds[random::below(sounds.len() as u32)? as usize];
+        if password.len() + sound.len() <= length {
+            password.push_str(sound);
+            vowel = !vowel;
+        }
+    }
+    Ok(password)
+}
+
+impl PasswordStore {
+    /// Generates a new password and stores it, like `pass generate` does.
+    ///
+    /// Returns the generated password. With `in_place`, only the first line of an
+    /// existing entry is replaced, with `force`, an existing entry is overwritten.
+    /// The length and character set of the [`policy`](Self::policy_for) of the entry
+    /// take precedence over the options.
+    ///
+    /// This operation is synchronous and will block until encryption completes.
+    pub fn generate(&self, pass_id: &str, options: &GenerateOptions) -> Result<String, Error> {
+        self.touch();
+        let options = match self.policy_for(pass_id)? {
+            Some(policy) => policy.apply(options),
+            None => options.clone(),
+        };
+        let password = generate_password(&options)?;
+        let path = self.checked_path(pass_id)?;
+        let exists = path.symlink_metadata().is_ok();
+
+        if exists && options.in_place {
+            self.edit_entry(pass_id, |contents| {
+                let rest = contents.split_once('\n').map(|(_, rest)| rest.to_string());
+                *contents = match rest {
+                    Some(rest) => format!("{password}\n{rest}"),
+                    None => format!("{password}\n"),
+                };
+            })?;
+        } else if exists && options.force {
+            let recipients = self.gpg_ids_for(pass_id)?;
+            let path = self.resolve_alias(&path);
+            self.ensure_writable(&path)?;
+            self.write_operation(
+                format!("Add generated password for {pass_id}."),
+                std::slice::from_ref(&path),
+                || {
+                    let contents = format!("{password}\n");
+                    operations::write_entry(
+                        &self.backend(),
+                        &path,
+                        contents.as_bytes(),
+                        &recipients,
+                    )
+                },
+            )?;
+        } else if exists {
+            return Err(Error::Pass(format!(
+                "an entry already exists for {pass_id}"
+            )));
+        } else {
+            let recipients = self.gpg_ids_for(pass_id)?;
+            self.write_operation(
+                format!("Add generated password for {pass_id}."),
+                std::slice::from_ref(&path),
+                || {
+                    let contents = format!("{password}\n");
+                    operations::write_new_entry(
+                        &self.backend(),
+                        &path,
+                        contents.as_bytes(),
+                        &recipients,
+                    )
+                },
+            )?;
+        }
+
+        if options.clip {
+            copy_to_clipboard(
+                &password,
+                &ClipboardOptions::default(),
+                Content::ExpiringSecret,
+            )?;
+        }
+        Ok(password)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_password_uses_character_set() {
+        let options = GenerateOptions {
+            length: 40,
+            no_symbols: true,
+            ..Default::default()
+        };
+        let password = generate_password(&options).unwrap();
+        assert_eq!(password.chars().count(), 40);
+        assert!(password.chars().all(|c| c.is_ascii_alphanumeric()));
+    }
+
+    #[test]
+    fn pronounceable_password_alternates_sounds() {
+        let options = GenerateOptions {
+            length: 15,
+            pronounceable: true,
+            ..Default::default()
+        };
+        let password = generate_password(&options).unwrap();
+        assert_eq!(password.len(), 15);
+        assert!(password.chars().all(|c| c.is_ascii_lowercase()));
+        assert!(password.contains(['a', 'e', 'i', 'o', 'u']));
+    }
+}
@@ -0,0 +1,102 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    process::Command,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::Error;
+
+/// Checks whether the password store is tracked by git.
+pub fn is_repository(store_dir: &Path) -> bool {
+    store_dir.join(".git").exists()
+}
+
+/// Runs git with the given arguments in the store directory and returns stdout.
+pub fn run(store_dir: &Path, args: &[&str]) -> Result<String, Error> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(store_dir)
+        .args(["-c", "core.quotePath=false"])
+        .args(args)
+        .output()?;
+    if !output.status.success() {
+        return Err(Error::Pass(format!(
+            "git {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    String::from_utf8(output.stdout).map_err(|e| e.into())
+}
+
+/// Returns the time of the last commit touching each `.gpg` file.
+///
+/// Paths are relative to the store directory.
+pub fn last_commit_times(store_dir: &Path) -> Result<HashMap<PathBuf, SystemTime>, Error> {
+    let log = run(
+        store_dir,
+        &[
+            "log",
+            "--relative",
+            "--name-only",
+            "--pretty=format:%x00%ct",
+            "--",
+            "*.gpg",
+        ],
+    )?;
+
+    let mut times = HashMap::new();
+    let mut commit_time = None;
+    for line in log.lines() {
+        if let Some(timestamp) = line.strip_prefix('\0') {
+            commit_time = timestamp
+                .parse()
+                .ok()
+                .map(|secs| UNIX_EPOCH + Duration::from_secs(secs));
+        } else if let (false, Some(time)) = (line.is_empty(), commit_time) {
+            // The log is ordered from newest to oldest
+            times.entry(PathBuf::from(line)).or_insert(time);
+        }
+    }
+    Ok(times)
+}
+
+/// Stages the given paths, including deletions, and commits only them.
+///
+/// Other changes the user staged stay staged. Fails if a path is outside of
+/// the store or not valid UTF-8.
+pub fn commit(store_dir: &Path, paths: &[PathBuf], message: &str) -> Result<(), Error> {
+    let relative_paths = paths
+        .iter()
+        .map(|path| {
+            path.strip_prefix(store_dir)
+                .ok()
+                .and_then(|path| path.to_str())
+                .ok_or_else(|| Error::Pass(format!("cannot commit {} with git", path.display())))
+        })
+        .collect::<Result<Vec<&str>, Error>>()?;
+    // Without a pathspec git would stage the whole worktree
+    if relative_paths.is_empty() {
+        return Ok(());
+    }
+    let mut add_args = vec!["add", "--all", "--"];
+    add_args.extend(&relative_paths);
+    run(store_dir, &add_args)?;
+
+    // Unchanged paths are left out, git refuses to commit paths it doesn't know
+    let mut diff_args = vec!["diff", "--cached", "--name-only", "-z", "--"];
+    diff_args.extend(&relative_paths);
+    let changed = run(store_dir, &diff_args)?;
+    let changed: Vec<&str> = changed
+        .split('\0')
+        .filter(|path| !path.is_empty())
+        .collect();
+    if changed.is_empty() {
+        return Ok(());
+    }
+    let mut commit_args = vec!["commit", "--quiet", "--message", message, "--"];
+    commit_args.extend(&changed);
+    run(store_dir, &commit_args)?;
+    Ok(())
+}
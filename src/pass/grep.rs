@@ -0,0 +1,113 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+};
+
+use super::PasswordStore;
+use crate::PasswordEvent;
+
+/// Maximal number of entries decrypted at the same time, more would only queue up
+/// at the GPG agent.
+const MAX_DECRYPTIONS: usize = 4;
+
+/// Replaces a matching first line, so that passwords are never returned.
+const REDACTED: &str = "[password]";
+
+/// An entry with decrypted lines containing the pattern of
+/// [`PasswordStore::grep`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrepMatch {
+    pub pass_id: String,
+    /// Matching lines with their line numbers starting at 1
+    pub lines: Vec<(usize, String)>,
+}
+
+impl PasswordStore {
+    /// Searches the decrypted contents of all entries for `pattern`, like
+    /// `pass grep`.
+    ///
+    /// The pattern is matched case-insensitively against every line. A matching
+    /// password on the first line is reported, but its text is redacted. Entries are
+    /// decrypted in parallel, a [`PasswordEvent::GrepProgress`] is sent for each of
+    /// them. Entries that can not be decrypted are skipped. Returns the matches
+    /// sorted by ID.
+    ///
+    /// This operation is synchronous and will block until all entries are decrypted.
+    pub fn grep(&self, pattern: &str) -> Vec<GrepMatch> {
+        self.touch();
+        let snapshot = self.snapshot();
+        let entries: Vec<&str> = snapshot.iter().map(|info| info.id.as_str()).collect();
+        let total = entries.len();
+        let pattern = pattern.to_lowercase();
+
+        let next = AtomicUsize::new(0);
+        let done = AtomicUsize::new(0);
+        let matches = Mutex::new(Vec::new());
+        let workers = thread::available_parallelism()
+            .map_or(1, |n| n.get())
+            .min(MAX_DECRYPTIONS)
+            .min(total);
+        thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|| {
+                    while let Some(pass_id) = entries.get(next.fetch_add(1, Ordering::Relaxed)) {
+                        if let Ok(contents) = self.decrypt_file(&self.entry_path(pass_id)) {
+                            let lines = matching_lines(&contents, &pattern);
+                            if !lines.is_empty() {
+                                matches
+                                    .lock()
+                                    .expect("another thread holding the lock paniced")
+                                    .push(GrepMatch {
+                                        pass_id: pass_id.to_string(),
+                                        lines,
+                                    });
+                            }
+                        }
+                        self.send_event(PasswordEvent::GrepProgress {
+                            pass_id: pass_id.to_string(),
+                            done: done.fetch_add(1, Ordering::Relaxed) + 1,
+                            total,
+                        });
+                    }
+                });
+            }
+        });
+
+        let mut matches = matches
+            .into_inner()
+            .expect("another thread holding the lock paniced");
+        matches.sort_by(|a, b| a.pass_id.cmp(&b.pass_id));
+        matches
+    }
+}
+
+/// Returns the numbered lines containing the lowercase `pattern`.
+fn matching_lines(contents: &str, pattern: &str) -> Vec<(usize, String)> {
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| line.to_lowercase().contains(pattern))
+        .map(|(i, line)| {
+            let text = if i == 0 { REDACTED } else { line };
+            (i + 1, text.to_string())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matching_lines_redacts_password() {
+        let contents = "hunter2\nlogin: Hunter\nurl: example.org\n";
+        assert_eq!(
+            matching_lines(contents, "hunter"),
+            vec![(1, REDACTED.to_string()), (2, "login: Hunter".to_string())]
+        );
+        assert!(matching_lines(contents, "missing").is_empty());
+    }
+}
@@ -0,0 +1,292 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use super::{atomic, git, permissions, PasswordStore, StoreLock};
+use crate::Error;
+
+/// Maximum number of write operations that can be undone.
+const HISTORY_LIMIT: usize = 100;
+
+/// Raw state of a file in the store, contents of entries stay encrypted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum FileState {
+    Missing,
+    File(Vec<u8>),
+    Symlink(PathBuf),
+}
+
+impl FileState {
+    fn capture(path: &Path) -> io::Result<Self> {
+        match path.symlink_metadata() {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::Missing),
+            Err(e) => Err(e),
+            Ok(metadata) if metadata.file_type().is_symlink() => {
+                Ok(Self::Symlink(fs::read_link(path)?))
+            }
+            Ok(_) => Ok(Self::File(fs::read(path)?)),
+        }
+    }
+
+    fn restore(&self, path: &Path) -> Result<(), Error> {
+        match path.symlink_metadata() {
+            Ok(_) => fs::remove_file(path)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+        if let Some(parent) = path.parent() {
+            if !matches!(self, Self::Missing) {
+                permissions::create_dir_all(parent)?;
+            }
+        }
+        match self {
+            Self::Missing => Ok(()),
+            Self::File(contents) => atomic::write(path, contents).map_err(|e| e.into()),
+            Self::Symlink(target) => PasswordStore::symlink(target, path),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct FileChange {
+    path: PathBuf,
+    before: FileState,
+    after: FileState,
+}
+
+/// A recorded write operation with the previous and new state of all touched files.
+#[derive(Debug, Clone)]
+struct Revision {
+    description: String,
+    changes: Vec<FileChange>,
+}
+
+impl Revision {
+    /// Fails if a file changed since the revision was applied, or since it was
+    /// undone if `undo` is `false`, e.g. by pass or a git pull.
+    fn ensure_unchanged(&self, undo: bool) -> Result<(), Error> {
+        for change in &self.changes {
+            let expected = if undo { &change.after } else { &change.before };
+            if FileState::capture(&change.path)? != *expected {
+                return Err(Error::Pass(format!(
+                    "{} was modified since, cannot {} {:?}",
+                    change.path.display(),
+                    if undo { "undo" } else { "redo" },
+                    self.description
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Undo and redo stacks of write operations.
+#[derive(Debug, Default)]
+pub struct History {
+    undo: Vec<Revision>,
+    redo: Vec<Revision>,
+}
+
+impl PasswordStore {
+    /// Runs a write operation that modifies the given files and records it.
+    ///
+    /// The [`StoreLock`] is held during the operation. The previous contents of the
+    /// files are retained for [`undo`](Self::undo), the change is committed when the
+    /// store is a git repository, and the entries for the files are refreshed.
+    pub(super) fn write_operation<T>(
+        &self,
+        description: String,
+        paths: &[PathBuf],
+        operation: impl FnOnce() -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let _lock = StoreLock::acquire(&self.store_dir)?;
+        let before = paths
+            .iter()
+            .map(|path| FileState::capture(path))
+            .collect::<io::Result<Vec<_>>>()?;
+        let result = operation();
+        self.refresh_entries(paths);
+        let value = result?;
+
+        let changes = paths
+            .iter()
+            .zip(before)
+            .map(|(path, before)| {
+                Ok(FileChange {
+                    path: path.clone(),
+                    before,
+                    after: FileState::capture(path)?,
+                })
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        self.commit(paths, &description)?;
+
+        let mut history = self.history();
+        history.redo.clear();
+        history.undo.push(Revision {
+            description,
+            changes,
+        });
+        if history.undo.len() > HISTORY_LIMIT {
+            history.undo.remove(0);
+        }
+        Ok(value)
+    }
+
+    /// Reverts the last write operation and returns its description.
+    ///
+    /// Returns `None` if there is nothing to undo. Fails without changing anything
+    /// if one of the files was modified since the operation, the operation stays
+    /// on the undo stack then.
+    pub fn undo(&self) -> Result<Option<String>, Error> {
+        self.touch();
+        let _lock = StoreLock::acquire(&self.store_dir)?;
+        let Some(revision) = self.history().undo.pop() else {
+            return Ok(None);
+        };
+        if let Err(e) = revision.ensure_unchanged(true) {
+            self.history().undo.push(revision);
+            return Err(e);
+        }
+        let result = self.apply(&revision, true);
+        let description = revision.description.clone();
+        self.history().redo.push(revision);
+        result.map(|_| Some(description))
+    }
+
+    /// Reapplies the last undone write operation and returns its description.
+    ///
+    /// Returns `None` if there is nothing to redo. Like [`undo`](Self::undo), fails
+    /// if one of the files was modified since.
+    pub fn redo(&self) -> Result<Option<String>, Error> {
+        self.touch();
+        let _lock = StoreLock::acquire(&self.store_dir)?;
+        let Some(revision) = self.history().redo.pop() else {
+            return Ok(None);
+        };
+        if let Err(e) = revision.ensure_unchanged(false) {
+            self.history().redo.push(revision);
+            return Err(e);
+        }
+        let result = self.apply(&revision, false);
+        let description = revision.description.clone();
+        self.history().undo.push(revision);
+        result.map(|_| Some(description))
+    }
+
+    /// Reverts the last destructive operation and returns its description.
+    ///
+    /// Write operations of the current session are undone first. When the history
+    /// is empty, e.g. after a restart, the most recently trashed entry is restored
+    /// instead. Returns `None` if there is nothing to revert.
+    pub fn undo_last(&self) -> Result<Option<String>, Error> {
+        if self.can_undo() {
+            return self.undo();
+        }
+        let Some(entry) = self.list_trash()?.into_iter().next() else {
+            return Ok(None);
+        };
+        self.restore_from_trash(&entry.id)?;
+        Ok(Some(format!("Restore {} from trash.", entry.id)))
+    }
+
+    /// Checks whether there is a write operation that can be undone.
+    pub fn can_undo(&self) -> bool {
+        !self.history().undo.is_empty()
+    }
+
+    /// Checks whether there is an undone write operation that can be redone.
+    pub fn can_redo(&self) -> bool {
+        !self.history().redo.is_empty()
+    }
+
+    /// Restores the files of the revision, the caller holds the [`StoreLock`].
+    fn apply(&self, revision: &Revision, undo: bool) -> Result<(), Error> {
+        let paths: Vec<PathBuf> = revision.changes.iter().map(|c| c.path.clone()).collect();
+        let result = if undo {
+            revision
+                .changes
+                .iter()
+                .rev()
+                .try_for_each(|change| change.before.restore(&change.path))
+        } else {
+            revision
+                .changes
+                .iter()
+                .try_for_each(|change| change.after.restore(&change.path))
+        };
+        self.refresh_entries(&paths);
+        result?;
+
+        let prefix = if undo { "Undo" } else { "Redo" };
+        self.commit(&paths, &format!("{prefix}: {}", revision.description))
+    }
+
+    /// Commits the paths to the git repositories of the stores containing them.
+    fn commit(&self, paths: &[PathBuf], message: &str) -> Result<(), Error> {
+        for (store_dir, paths) in self.paths_by_store(paths) {
+            if git::is_repository(store_dir) {
+                git::commit(store_dir, &paths, message)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pass::test_dir::TestDir;
+
+    #[test]
+    fn undo_and_redo_alias() {
+        let store_dir = TestDir::new("alias");
+        fs::write(store_dir.join("entry.gpg"), b"cipher").unwrap();
+        let mut store = PasswordStore::default();
+        store.store_dir = store_dir.to_path_buf();
+
+        store.alias("entry", "web/alias").unwrap();
+        assert!(store_dir.join("web/alias.gpg").is_file());
+        assert_eq!(store.snapshot().len(), 1);
+
+        assert!(store.undo().unwrap().is_some());
+        assert!(!store_dir.join("web/alias.gpg").exists());
+        assert!(store.snapshot().is_empty());
+
+        assert!(store.redo().unwrap().is_some());
+        assert_eq!(
+            fs::read(store_dir.join("web/alias.gpg")).unwrap(),
+            b"cipher"
+        );
+
+        // Changed by another program in the meantime
+        fs::remove_file(store_dir.join("web/alias.gpg")).unwrap();
+        fs::write(store_dir.join("web/alias.gpg"), b"other cipher").unwrap();
+        assert!(store.undo().is_err());
+        assert!(store.can_undo());
+        assert_eq!(
+            fs::read(store_dir.join("web/alias.gpg")).unwrap(),
+            b"other cipher"
+        );
+    }
+
+    #[test]
+    fn undo_last_restores_from_trash() {
+        let store_dir = TestDir::new("trash");
+        fs::write(store_dir.join("entry.gpg"), b"cipher").unwrap();
+        let mut store = PasswordStore::default();
+        store.store_dir = store_dir.to_path_buf();
+
+        store.trash("entry").unwrap();
+        assert!(!store_dir.join("entry.gpg").exists());
+
+        // A new session has no history, the trash is used instead
+        let mut store = PasswordStore::default();
+        store.store_dir = store_dir.to_path_buf();
+        assert!(store.undo_last().unwrap().is_some());
+        assert_eq!(fs::read(store_dir.join("entry.gpg")).unwrap(), b"cipher");
+        assert!(store.undo_last().unwrap().is_some());
+        assert!(!store_dir.join("entry.gpg").exists());
+    }
+}
@@ -0,0 +1,181 @@
+use std::{fs, path::Path};
+
+/// File in the store directory with patterns of entries to hide.
+pub(super) const IGNORE_FILE: &str = ".passepartout-ignore";
+
+/// Patterns of paths to exclude from the entry list, in gitignore syntax.
+///
+/// Patterns without a slash match the name at any depth, other patterns are
+/// relative to the store directory. A trailing slash matches only directories,
+/// `*` and `?` match within a name, `**` across directories and `[...]` one of a
+/// set of characters. A leading `!` includes paths again. The last matching pattern
+/// wins, but nothing below an excluded directory is included again.
+#[derive(Debug, Clone, Default)]
+pub(super) struct IgnoreRules {
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    pattern: Vec<char>,
+    negated: bool,
+    dir_only: bool,
+    anchored: bool,
+}
+
+impl IgnoreRules {
+    /// Reads the ignore file of the store directory, followed by more patterns.
+    pub(super) fn load(store_dir: &Path, patterns: &[String]) -> Self {
+        let file = fs::read_to_string(store_dir.join(IGNORE_FILE)).unwrap_or_default();
+        let mut rules = Self::default();
+        for line in file.lines().chain(patterns.iter().map(String::as_str)) {
+            rules.add(line);
+        }
+        rules
+    }
+
+    fn add(&mut self, line: &str) {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            return;
+        }
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let (dir_only, line) = match line.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let anchored = line.contains('/');
+        self.rules.push(Rule {
+            pattern: line.trim_start_matches('/').chars().collect(),
+            negated,
+            dir_only,
+            anchored,
+        });
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Checks whether a path relative to the store directory is excluded.
+    pub(super) fn is_ignored(&self, relative_path: &str, is_dir: bool) -> bool {
+        let path: Vec<char> = relative_path.chars().collect();
+        let name_start = path.iter().rposition(|c| *c == '/').map_or(0, |i| i + 1);
+        let mut ignored = false;
+        for rule in &self.rules {
+            if rule.dir_only && !is_dir {
+                continue;
+            }
+            let text = if rule.anchored {
+                &path[..]
+            } else {
+                &path[name_start..]
+            };
+            if matches(&rule.pattern, text) {
+                ignored = !rule.negated;
+            }
+        }
+        ignored
+    }
+}
+
+/// Matches a shell-style glob pattern against the whole path.
+///
+/// `*` and `?` do not match a `/`, `**` matches any number of directories.
+pub(super) fn glob_matches(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let path: Vec<char> = path.chars().collect();
+    matches(&pattern, &path)
+}
+
+/// Matches a glob pattern against the whole text.
+fn matches(pattern: &[char], text: &[char]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        ['*', '*', '/', rest @ ..] => {
+            matches(rest, text)
+                || (0..text.len()).any(|i| text[i] == '/' && matches(rest, &text[i + 1..]))
+        }
+        ['*', '*', rest @ ..] => (0..=text.len()).any(|i| matches(rest, &text[i..])),
+        ['*', rest @ ..] => {
+            let name_len = text.iter().position(|c| *c == '/').unwrap_or(text.len());
+            (0..=name_len).any(|i| matches(rest, &text[i..]))
+        }
+        ['?', rest @ ..] => text.first().is_some_and(|c| *c != '/') && matches(rest, &text[1..]),
+        ['[', rest @ ..] => match (text.first(), rest.iter().position(|c| *c == ']')) {
+            (Some(c), Some(end)) if end > 0 => {
+                let (set, negated) = match &rest[..end] {
+                    ['!', set @ ..] => (set, true),
+                    set => (set, false),
+                };
+                *c != '/' && in_set(set, *c) != negated && matches(&rest[end + 1..], &text[1..])
+            }
+            _ => text.first() == Some(&'[') && matches(rest, &text[1..]),
+        },
+        ['\\', c, rest @ ..] | [c, rest @ ..] => {
+            text.first() == Some(c) && matches(rest, &text[1..])
+        }
+    }
+}
+
+fn in_set(set: &[char], c: char) -> bool {
+    let mut i = 0;
+    while i < set.len() {
+        if i + 2 < set.len() && set[i + 1] == '-' {
+            if (set[i]..=set[i + 2]).contains(&c) {
+                return true;
+            }
+            i += 3;
+        } else {
+            if set[i] == c {
+                return true;
+            }
+            i += 1;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gitignore_patterns() {
+        let mut rules = IgnoreRules::default();
+        for line in [
+            "# comment",
+            "archive/",
+            "*.old",
+            "/work/tmp-?",
+            "**/cache/**",
+            "!keep.old",
+        ] {
+            rules.add(line);
+        }
+        assert!(rules.is_ignored("archive", true));
+        assert!(rules.is_ignored("deep/archive", true));
+        assert!(!rules.is_ignored("archive", false));
+        assert!(rules.is_ignored("mail/bank.old", false));
+        assert!(!rules.is_ignored("keep.old", false));
+        assert!(rules.is_ignored("work/tmp-1", false));
+        assert!(!rules.is_ignored("other/work/tmp-1", false));
+        assert!(rules.is_ignored("a/cache/b/c", false));
+        assert!(matches(&"[a-c]x".chars().collect::<Vec<_>>(), &['b', 'x']));
+        assert!(!matches(
+            &"[!a-c]x".chars().collect::<Vec<_>>(),
+            &['b', 'x']
+        ));
+    }
+
+    #[test]
+    fn glob_matches_whole_id() {
+        assert!(glob_matches("web/*/admin", "web/example.org/admin"));
+        assert!(!glob_matches("web/*/admin", "web/a/b/admin"));
+        assert!(glob_matches("web/**/admin", "web/a/b/admin"));
+        assert!(!glob_matches("web/*", "web/a/admin"));
+    }
+}
@@ -0,0 +1,126 @@
+use icu::normalizer::ComposingNormalizer;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, Weak},
+};
+
+use super::{PasswordInfo, PasswordStore, StoreSnapshot};
+
+/// Resolves IDs that differ only in case, Unicode normalization or separators,
+/// like `Web/GitHub` and `web//github`.
+///
+/// Stores edited on case-insensitive file systems often end up with such variants.
+#[derive(Debug, Clone, Default)]
+pub struct IdIndex {
+    ids: HashMap<String, Vec<String>>,
+}
+
+impl IdIndex {
+    /// Indexes the IDs of the entries.
+    pub fn new(entries: &[PasswordInfo]) -> Self {
+        let mut ids: HashMap<String, Vec<String>> = HashMap::new();
+        for info in entries {
+            ids.entry(normalize_id(&info.id))
+                .or_default()
+                .push(info.id.clone());
+        }
+        Self { ids }
+    }
+
+    /// Returns the IDs of all entries that match the ID after normalizing both.
+    pub fn lookup(&self, pass_id: &str) -> &[String] {
+        self.ids
+            .get(&normalize_id(pass_id))
+            .map_or(&[], Vec::as_slice)
+    }
+}
+
+impl StoreSnapshot {
+    /// Builds an index of the entries for case-insensitive lookups.
+    pub fn index(&self) -> IdIndex {
+        IdIndex::new(self)
+    }
+}
+
+impl PasswordStore {
+    /// Returns the entry with the ID, ignoring differences in case, Unicode
+    /// normalization and separators if there is no exact match.
+    ///
+    /// Returns `None` if no entry or multiple variants match, see
+    /// [`lookup_variants`](Self::lookup_variants).
+    pub fn lookup(&self, pass_id: &str) -> Option<PasswordInfo> {
+        if let Some(info) = self.get(pass_id) {
+            return Some(info);
+        }
+        match self.index().lookup(pass_id) {
+            [pass_id] => self.get(pass_id),
+            _ => None,
+        }
+    }
+
+    /// Returns all entries whose IDs match the ID after normalizing both, e.g. to
+    /// let the user pick one of several case variants.
+    pub fn lookup_variants(&self, pass_id: &str) -> Vec<PasswordInfo> {
+        let snapshot = self.snapshot();
+        self.index()
+            .lookup(pass_id)
+            .iter()
+            .filter_map(|pass_id| snapshot.get(pass_id).cloned())
+            .collect()
+    }
+
+    /// Returns the index of the current entries, which is rebuilt after they changed.
+    fn index(&self) -> Arc<IdIndex> {
+        let snapshot = self.snapshot();
+        let mut cached = self
+            .index
+            .lock()
+            .expect("another thread holding the lock paniced");
+        match &*cached {
+            Some((indexed, index)) if Weak::ptr_eq(indexed, &snapshot.downgrade()) => {
+                Arc::clone(index)
+            }
+            _ => {
+                let index = Arc::new(snapshot.index());
+                *cached = Some((snapshot.downgrade(), Arc::clone(&index)));
+                index
+            }
+        }
+    }
+}
+
+/// The index of the entries of a store with the entries it was built from.
+pub(super) type CachedIndex = Mutex<Option<(Weak<Vec<PasswordInfo>>, Arc<IdIndex>)>>;
+
+/// Converts the ID to lowercase NFC and removes empty components, converting
+/// backslashes to slashes.
+fn normalize_id(pass_id: &str) -> String {
+    let composed = ComposingNormalizer::new_nfc().normalize(pass_id);
+    composed
+        .to_lowercase()
+        .split(['/', '\\'])
+        .filter(|component| !component.is_empty())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn lookup_ignores_case_and_separators() {
+        let metadata = fs::metadata(env!("CARGO_MANIFEST_DIR")).unwrap();
+        let entries: Vec<PasswordInfo> = ["Web/GitHub", "web/gitlab", "web/Gitlab", "caf\u{e9}"]
+            .into_iter()
+            .map(|id| PasswordInfo::new(id.to_string(), metadata.clone()))
+            .collect();
+        let index = IdIndex::new(&entries);
+
+        assert_eq!(index.lookup("web//github/"), ["Web/GitHub"]);
+        assert_eq!(index.lookup("WEB\\GITLAB").len(), 2);
+        assert_eq!(index.lookup("cafe\u{301}"), ["caf\u{e9}"]);
+        assert!(index.lookup("web").is_empty());
+    }
+}
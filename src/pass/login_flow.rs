@@ -0,0 +1,57 @@
+use super::PasswordStore;
+use crate::{
+    clipboard::{copy_to_clipboard, ClipboardOptions, Content},
+    Error, PasswordEvent,
+};
+
+impl PasswordStore {
+    /// Copies the login of an entry, waits until it was pasted and then copies the
+    /// password, for the usual login forms with two fields.
+    ///
+    /// `wait_for_paste` blocks until the login was pasted, e.g. until the user
+    /// confirms or a timeout expires, and returns `false` to cancel before the
+    /// password is copied. Sends a [`PasswordEvent::LoginCopied`] and a
+    /// [`PasswordEvent::PasswordCopied`] after each step. Returns whether the
+    /// password was copied. Both are cleared after the expiry of the options, with a
+    /// [`PasswordEvent::ClipboardCleared`] sent to the event sender of the store.
+    ///
+    /// The entry is only decrypted once, before the login is copied.
+    pub fn copy_login_then_password<F>(
+        &self,
+        pass_id: &str,
+        options: &ClipboardOptions,
+        wait_for_paste: F,
+    ) -> Result<bool, Error>
+    where
+        F: FnOnce() -> bool,
+    {
+        self.touch();
+        let mut options = options.clone();
+        if let (None, Some(event_tx)) = (&options.event_tx, self.event_sender()) {
+            options.event_tx = Some((pass_id.to_string(), event_tx.clone()));
+        }
+        let contents = self.decrypt_file(&self.checked_path(pass_id)?)?;
+        self.record_use(pass_id);
+        let mut lines = contents.lines();
+        let password = lines
+            .next()
+            .ok_or_else(|| Error::Pass("no password found".to_string()))?;
+        let login = lines
+            .next()
+            .ok_or_else(|| Error::Pass("no login found".to_string()))?;
+
+        copy_to_clipboard(login, &options, Content::ExpiringSecret)?;
+        self.send_event(PasswordEvent::LoginCopied {
+            pass_id: pass_id.to_string(),
+        });
+        if !wait_for_paste() {
+            return Ok(false);
+        }
+
+        copy_to_clipboard(password, &options, Content::ExpiringSecret)?;
+        self.send_event(PasswordEvent::PasswordCopied {
+            pass_id: pass_id.to_string(),
+        });
+        Ok(true)
+    }
+}
@@ -0,0 +1,315 @@
+use std::{fs, io, path::PathBuf};
+use toml::{Table, Value};
+
+use super::{atomic, PasswordInfo, PasswordStore};
+use crate::Error;
+
+/// Location of the metadata sidecar relative to the store directory.
+const METADATA_FILE: &str = ".passepartout/meta.toml";
+
+/// Non-secret display hints for an entry, stored unencrypted in a sidecar file.
+///
+/// The sidecar `.passepartout/meta.toml` in the store directory maps pass IDs to
+/// tables with the optional keys `title`, `icon`, `color`, `pinned`, `tags` and
+/// `favorite`. Since it is not encrypted, it must not contain any sensitive
+/// information. Changes are committed like those of entries if the store is a git
+/// repository.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DisplayMetadata {
+    /// Title to display instead of the pass ID
+    pub title: Option<String>,
+    /// Icon name, path or favicon URL
+    pub icon: Option<String>,
+    /// Color, e.g. as hex code
+    pub color: Option<String>,
+    /// Position among pinned entries, lower values come first
+    pub pinned: Option<i64>,
+    /// Tags for organizing entries independently of their folders
+    pub tags: Vec<String>,
+    pub favorite: bool,
+}
+
+impl DisplayMetadata {
+    fn from_table(table: &Table) -> Self {
+        let string = |key: &str| table.get(key).and_then(Value::as_str).map(String::from);
+        Self {
+            title: string("title"),
+            icon: string("icon"),
+            color: string("color"),
+            pinned: table.get("pinned").and_then(Value::as_integer),
+            tags: table
+                .get("tags")
+                .and_then(Value::as_array)
+                .map(|tags| {
+                    tags.iter()
+                        .filter_map(Value::as_str)
+                        .map(String::from)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            favorite: table
+                .get("favorite")
+                .and_then(Value::as_bool)
+                .unwrap_or_default(),
+        }
+    }
+
+    fn to_table(&self) -> Table {
+        let mut table = Table::new();
+        let fields = [
+            ("title", &self.title),
+            ("icon", &self.icon),
+            ("color", &self.color),
+        ];
+        for (key, value) in fields {
+            if let Some(value) = value {
+                table.insert(key.to_string(), Value::String(value.clone()));
+            }
+        }
+        if let Some(pinned) = self.pinned {
+            table.insert("pinned".to_string(), Value::Integer(pinned));
+        }
+        if !self.tags.is_empty() {
+            let tags = self.tags.iter().cloned().map(Value::String).collect();
+            table.insert("tags".to_string(), Value::Array(tags));
+        }
+        if self.favorite {
+            table.insert("favorite".to_string(), Value::Boolean(true));
+        }
+        table
+    }
+}
+
+impl PasswordStore {
+    /// Returns the display metadata for an entry, if any is stored.
+    pub fn display_metadata(&self, pass_id: &str) -> Result<Option<DisplayMetadata>, Error> {
+        Ok(self
+            .read_metadata()?
+            .get(pass_id)
+            .and_then(Value::as_table)
+            .map(DisplayMetadata::from_table))
+    }
+
+    /// Returns the display metadata of all entries.
+    pub fn all_display_metadata(&self) -> Result<Vec<(String, DisplayMetadata)>, Error> {
+        Ok(self
+            .read_metadata()?
+            .iter()
+            .filter_map(|(pass_id, value)| {
+                value
+                    .as_table()
+                    .map(|table| (pass_id.clone(), DisplayMetadata::from_table(table)))
+            })
+            .collect())
+    }
+
+    /// Stores the display metadata for an entry, `None` removes it.
+    pub fn set_display_metadata(
+        &self,
+        pass_id: &str,
+        metadata: Option<DisplayMetadata>,
+    ) -> Result<(), Error> {
+        let mut table = self.read_metadata()?;
+        match metadata {
+            Some(metadata) if metadata != DisplayMetadata::default() => {
+                table.insert(pass_id.to_string(), Value::Table(metadata.to_table()));
+            }
+            _ => {
+                table.remove(pass_id);
+            }
+        }
+        self.write_metadata(format!("Update display metadata for {pass_id}."), &table)
+    }
+
+    /// Adds a tag to an entry, tags are compared ignoring case.
+    pub fn add_tag(&self, pass_id: &str, tag: &str) -> Result<(), Error> {
+        let tag = tag.trim();
+        if tag.is_empty() {
+            return Err(Error::Pass("tags must not be empty".to_string()));
+        }
+        let mut metadata = self.display_metadata(pass_id)?.unwrap_or_default();
+        if metadata.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            return Ok(());
+        }
+        metadata.tags.push(tag.to_string());
+        self.set_display_metadata(pass_id, Some(metadata))
+    }
+
+    /// Removes a tag from an entry, ignoring case.
+    pub fn remove_tag(&self, pass_id: &str, tag: &str) -> Result<(), Error> {
+        let Some(mut metadata) = self.display_metadata(pass_id)? else {
+            return Ok(());
+        };
+        let count = metadata.tags.len();
+        metadata
+            .tags
+            .retain(|t| !t.eq_ignore_ascii_case(tag.trim()));
+        if metadata.tags.len() == count {
+            return Ok(());
+        }
+        self.set_display_metadata(pass_id, Some(metadata))
+    }
+
+    /// Marks an entry as favorite or removes the mark.
+    pub fn set_favorite(&self, pass_id: &str, favorite: bool) -> Result<(), Error> {
+        let mut metadata = self.display_metadata(pass_id)?.unwrap_or_default();
+        if metadata.favorite == favorite {
+            return Ok(());
+        }
+        metadata.favorite = favorite;
+        self.set_display_metadata(pass_id, Some(metadata))
+    }
+
+    /// Returns all tags of the entries sorted and without duplicates.
+    pub fn tags(&self) -> Result<Vec<String>, Error> {
+        let mut tags: Vec<String> = self
+            .all_display_metadata()?
+            .into_iter()
+            .flat_map(|(_, metadata)| metadata.tags)
+            .collect();
+        tags.sort_by_key(|tag| tag.to_lowercase());
+        tags.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+        Ok(tags)
+    }
+
+    /// Returns the entries with the tag, ignoring case.
+    pub fn filter_by_tag(&self, tag: &str) -> Result<Vec<PasswordInfo>, Error> {
+        self.entries_with(|metadata| metadata.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+    }
+
+    /// Returns the entries marked as favorite.
+    pub fn favorites(&self) -> Result<Vec<PasswordInfo>, Error> {
+        self.entries_with(|metadata| metadata.favorite)
+    }
+
+    /// Returns the existing entries whose metadata matches, sorted by ID.
+    fn entries_with<F>(&self, matches: F) -> Result<Vec<PasswordInfo>, Error>
+    where
+        F: Fn(&DisplayMetadata) -> bool,
+    {
+        let snapshot = self.snapshot();
+        let mut entries: Vec<PasswordInfo> = self
+            .all_display_metadata()?
+            .into_iter()
+            .filter(|(_, metadata)| matches(metadata))
+            .filter_map(|(pass_id, _)| snapshot.get(&pass_id).cloned())
+            .collect();
+        entries.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(entries)
+    }
+
+    /// Moves the display metadata of an entry to a new pass ID.
+    ///
+    /// This keeps the sidecar consistent when entries are renamed with other tools.
+    pub fn rename_display_metadata(&self, old_id: &str, new_id: &str) -> Result<(), Error> {
+        if !self.has_display_metadata(old_id)? {
+            return Ok(());
+        }
+        let path = self.metadata_path();
+        self.write_operation(
+            format!("Move display metadata from {old_id} to {new_id}."),
+            std::slice::from_ref(&path),
+            || self.move_display_metadata(old_id, new_id),
+        )
+    }
+
+    /// Checks whether the sidecar has an entry for the pass ID.
+    pub(super) fn has_display_metadata(&self, pass_id: &str) -> Result<bool, Error> {
+        Ok(self.read_metadata()?.contains_key(pass_id))
+    }
+
+    /// Moves the display metadata without recording a write operation, for
+    /// callers already running one that includes [`metadata_path`](Self::metadata_path).
+    pub(super) fn move_display_metadata(&self, old_id: &str, new_id: &str) -> Result<(), Error> {
+        let mut table = self.read_metadata()?;
+        let Some(value) = table.remove(old_id) else {
+            return Ok(());
+        };
+        table.insert(new_id.to_string(), value);
+        atomic::write(&self.metadata_path(), to_toml(&table).as_bytes()).map_err(|e| e.into())
+    }
+
+    pub(super) fn metadata_path(&self) -> PathBuf {
+        self.store_dir.join(METADATA_FILE)
+    }
+
+    fn read_metadata(&self) -> Result<Table, Error> {
+        match fs::read_to_string(self.metadata_path()) {
+            Ok(contents) => Ok(contents.parse()?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Table::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn write_metadata(&self, description: String, table: &Table) -> Result<(), Error> {
+        let path = self.metadata_path();
+        self.write_operation(description, std::slice::from_ref(&path), || {
+            atomic::write(&path, to_toml(table).as_bytes()).map_err(|e| e.into())
+        })
+    }
+}
+
+/// Serializes the metadata table with one TOML table per pass ID.
+fn to_toml(table: &Table) -> String {
+    let mut output = String::new();
+    for (pass_id, value) in table {
+        let Some(entry) = value.as_table() else {
+            continue;
+        };
+        output.push_str(&format!("[{}]\n", quote(pass_id)));
+        for (key, value) in entry {
+            match value {
+                Value::String(text) => output.push_str(&format!("{key} = {}\n", quote(text))),
+                Value::Integer(number) => output.push_str(&format!("{key} = {number}\n")),
+                Value::Boolean(flag) => output.push_str(&format!("{key} = {flag}\n")),
+                Value::Array(values) => {
+                    let values: Vec<String> =
+                        values.iter().filter_map(Value::as_str).map(quote).collect();
+                    output.push_str(&format!("{key} = [{}]\n", values.join(", ")));
+                }
+                _ => {}
+            }
+        }
+        output.push('\n');
+    }
+    output
+}
+
+fn quote(text: &str) -> String {
+    let mut quoted = String::from('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04X}", u32::from(c))),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metadata_round_trip() {
+        let metadata = DisplayMetadata {
+            title: Some("Git\"Hub\"".to_string()),
+            icon: None,
+            color: Some("#000".to_string()),
+            pinned: Some(2),
+            tags: vec!["work".to_string(), "2\"fa".to_string()],
+            favorite: true,
+        };
+        let mut table = Table::new();
+        table.insert("web/git hub".to_string(), Value::Table(metadata.to_table()));
+
+        let parsed: Table = to_toml(&table).parse().unwrap();
+        let entry = parsed["web/git hub"].as_table().unwrap();
+        assert_eq!(DisplayMetadata::from_table(entry), metadata);
+    }
+}
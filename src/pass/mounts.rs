@@ -0,0 +1,91 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use super::PasswordStore;
+use crate::Error;
+
+impl PasswordStore {
+    /// Adds the mounts of gopass as other stores, see [`add_store`](Self::add_store).
+    ///
+    /// The mounts are read from `$GOPASS_CONFIG` or `~/.config/gopass/config`. Each
+    /// mount is an independent store with its own `.gpg-id` files and git
+    /// repository, changes to its entries are committed there. Returns the names of
+    /// the added mounts.
+    pub fn add_gopass_mounts(&mut self) -> Result<Vec<String>, Error> {
+        let config_path = match env::var_os("GOPASS_CONFIG") {
+            Some(path) => PathBuf::from(path),
+            None => dirs::config_dir()
+                .ok_or_else(|| Error::Pass("could not determine config directory".to_string()))?
+                .join("gopass/config"),
+        };
+        let mounts = parse_mounts(&fs::read_to_string(config_path)?);
+        for (name, store_dir) in &mounts {
+            self.mount(name, expand_home(store_dir));
+        }
+        self.reload();
+        Ok(mounts.into_iter().map(|(name, _)| name).collect())
+    }
+
+    /// Groups paths by the store containing them.
+    pub(super) fn paths_by_store<'a>(&'a self, paths: &[PathBuf]) -> Vec<(&'a Path, Vec<PathBuf>)> {
+        let mut stores: Vec<(&Path, Vec<PathBuf>)> = Vec::new();
+        for path in paths {
+            let store_dir = self.store_root(path);
+            match stores.iter_mut().find(|(dir, _)| *dir == store_dir) {
+                Some((_, paths)) => paths.push(path.clone()),
+                None => stores.push((store_dir, vec![path.clone()])),
+            }
+        }
+        stores
+    }
+}
+
+/// Returns the named mounts of a gopass config in git config syntax.
+///
+/// The unnamed `[mounts]` section holds the root store, which is the store
+/// directory itself.
+fn parse_mounts(config: &str) -> Vec<(String, String)> {
+    let mut mounts = Vec::new();
+    let mut section = None;
+    for line in config.lines().map(str::trim) {
+        if line.starts_with('[') {
+            section = line
+                .strip_prefix("[mounts \"")
+                .and_then(|rest| rest.strip_suffix("\"]"))
+                .map(String::from);
+        } else if let (Some(name), Some((key, value))) = (&section, line.split_once('=')) {
+            if key.trim() == "path" {
+                mounts.push((name.clone(), value.trim().trim_matches('"').to_string()));
+            }
+        }
+    }
+    mounts
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(relative), Some(home)) => home.join(relative),
+        _ => PathBuf::from(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_gopass_mounts() {
+        let config = "[core]\n\tautosync = true\n[mounts]\n\tpath = /stores/root\n\
+                      [mounts \"work\"]\n\tpath = /stores/work\n[mounts \"team/ops\"]\n\
+                      \tpath = \"~/ops\"\n";
+        assert_eq!(
+            parse_mounts(config),
+            [
+                ("work".to_string(), "/stores/work".to_string()),
+                ("team/ops".to_string(), "~/ops".to_string())
+            ]
+        );
+    }
+}
@@ -1,12 +1,22 @@
-use std::{path::Path, time};
+use std::{path::Path, time, time::Duration};
 use totp_rs::TOTP;
 
-use super::cryptography::decrypt;
-use crate::{clipboard::copy_to_clipboard, Error};
+use super::{
+    atomic,
+    cryptography::{
+        decrypt, decrypt_bytes_with_info, decrypt_lines, Backend, CryptoBackend, DecryptionInfo,
+    },
+    fields,
+    timeout::{run_with_limits, CancellationHandle},
+};
+use crate::{
+    clipboard::{copy_rotating, copy_to_clipboard, ClipboardOptions, Content},
+    Error,
+};
 
 /// Copies the password ID to the system clipboard.
-pub fn copy_id(pass_id: String) -> Result<(), Error> {
-    copy_to_clipboard(&pass_id, false)
+pub fn copy_id(pass_id: String, options: &ClipboardOptions) -> Result<(), Error> {
+    copy_to_clipboard(&pass_id, options, Content::Text)
 }
 
 /// Retrieves the contents of a password file.
@@ -17,55 +27,192 @@ pub fn decrypt_password_file(file_path: &Path) -> Result<String, Error> {
     decrypt(&cipher)
 }
 
-/// Copies the password from a file to the system clipboard, will be cleared after 45 seconds.
+/// Retrieves the first lines of a password file.
+///
+/// Decryption stops after the requested number of lines, which avoids decrypting
+/// large attachments or notes when only the password is needed. The rest of the
+/// file is not checked for integrity.
+pub fn decrypt_first_lines(file_path: &Path, lines: usize) -> Result<String, Error> {
+    let cipher = std::fs::read(file_path)?;
+    decrypt_lines(&cipher, lines)
+}
+
+/// Retrieves the contents of a password file, giving up after `timeout` or when
+/// `cancellation` is cancelled.
+///
+/// Returns [`Error::Timeout`] or [`Error::Cancelled`] instead of blocking forever,
+/// e.g. when the pinentry waits on another terminal. Without a timeout the
+/// cancellation is only checked before decrypting.
+pub fn decrypt_password_file_with_timeout(
+    file_path: &Path,
+    timeout: Option<Duration>,
+    cancellation: &CancellationHandle,
+) -> Result<String, Error> {
+    let cipher = std::fs::read(file_path)?;
+    run_with_limits(timeout, cancellation, move || decrypt(&cipher))
+}
+
+/// Retrieves the contents of a password file together with details about the key
+/// that decrypted it.
 ///
 /// This operation is synchronous and will block until decryption completes.
-pub fn copy_password(file_path: &Path) -> Result<(), Error> {
-    // Decrypt file and extract password on first line
-    let file_contents = decrypt_password_file(file_path)?;
+pub fn decrypt_with_info(file_path: &Path) -> Result<(String, DecryptionInfo), Error> {
+    let cipher = std::fs::read(file_path)?;
+    let (plain, info) = decrypt_bytes_with_info(&cipher)?;
+    Ok((String::from_utf8(plain)?, info))
+}
+
+/// Retrieves the contents of a password file holding arbitrary binary data.
+///
+/// This operation is synchronous and will block until decryption completes.
+pub fn decrypt_binary_file(file_path: &Path) -> Result<Vec<u8>, Error> {
+    read_entry(&Backend::Gpgme, file_path)
+}
+
+/// Encrypts the contents for the recipients and writes them to a new password file.
+///
+/// Missing parent directories are created, an existing file is not overwritten. The
+/// file is written atomically, so that the entry is never left truncated.
+///
+/// This operation is synchronous and will block until encryption completes.
+pub fn insert_entry(file_path: &Path, contents: &str, recipients: &[String]) -> Result<(), Error> {
+    insert_binary_entry(file_path, contents.as_bytes(), recipients)
+}
+
+/// Encrypts binary data for the recipients and writes it to a new password file.
+///
+/// Missing parent directories are created, an existing file is not overwritten. The
+/// file is written atomically, so that the entry is never left truncated.
+///
+/// This operation is synchronous and will block until encryption completes.
+pub fn insert_binary_entry(
+    file_path: &Path,
+    contents: &[u8],
+    recipients: &[String],
+) -> Result<(), Error> {
+    write_new_entry(&Backend::Gpgme, file_path, contents, recipients)
+}
+
+/// Decrypts a password file with the backend.
+pub(super) fn read_entry(backend: &dyn CryptoBackend, file_path: &Path) -> Result<Vec<u8>, Error> {
+    let cipher = std::fs::read(file_path)?;
+    backend.decrypt(&cipher)
+}
+
+/// Encrypts the contents with the backend and writes them to a new password file.
+pub(super) fn write_new_entry(
+    backend: &dyn CryptoBackend,
+    file_path: &Path,
+    contents: &[u8],
+    recipients: &[String],
+) -> Result<(), Error> {
+    let cipher = backend.encrypt(contents, recipients)?;
+    atomic::write_new(file_path, &cipher)?;
+    Ok(())
+}
+
+/// Encrypts the contents with the backend and replaces a password file.
+pub(super) fn write_entry(
+    backend: &dyn CryptoBackend,
+    file_path: &Path,
+    contents: &[u8],
+    recipients: &[String],
+) -> Result<(), Error> {
+    let cipher = backend.encrypt(contents, recipients)?;
+    atomic::write(file_path, &cipher)?;
+    Ok(())
+}
+
+/// Copies the password from a file to the system clipboard, will be cleared after the
+/// expiry of the options.
+///
+/// This operation is synchronous and will block until decryption completes.
+pub fn copy_password(file_path: &Path, options: &ClipboardOptions) -> Result<(), Error> {
+    // Decrypt only the first line holding the password
+    let file_contents = decrypt_first_lines(file_path, 1)?;
     let password = file_contents
         .lines()
         .next()
         .ok_or_else(|| Error::Pass("no password found".to_string()))?;
 
-    copy_to_clipboard(password, true)
+    copy_to_clipboard(password, options, Content::ExpiringSecret)
 }
 
-/// Copies the login from a file to the system clipboard, will be cleared after 45 seconds.
+/// Copies the login from a file to the system clipboard, will be cleared after the
+/// expiry of the options.
 ///
 /// This operation is synchronous and will block until decryption completes.
-pub fn copy_login(file_path: &Path) -> Result<(), Error> {
-    // Decrypt file and extract login on second line
-    let file_contents = decrypt_password_file(file_path)?;
+pub fn copy_login(file_path: &Path, options: &ClipboardOptions) -> Result<(), Error> {
+    // Decrypt only up to the second line holding the login
+    let file_contents = decrypt_first_lines(file_path, 2)?;
     let login = file_contents
         .lines()
         .nth(1)
         .ok_or_else(|| Error::Pass("no login found".to_string()))?;
 
-    copy_to_clipboard(login, true)
+    copy_to_clipboard(login, options, Content::ExpiringSecret)
+}
+
+/// Copies the value of a `key: value` line from a file to the system clipboard, will
+/// be cleared after the expiry of the options.
+///
+/// The key is matched case-insensitively, e.g. for PINs or API tokens stored in
+/// additional lines.
+///
+/// This operation is synchronous and will block until decryption completes.
+pub fn copy_field(file_path: &Path, key: &str, options: &ClipboardOptions) -> Result<(), Error> {
+    let file_contents = decrypt_password_file(file_path)?;
+    let value = fields::find_field(&file_contents, key)
+        .ok_or_else(|| Error::Pass(format!("no field {key} found")))?;
+
+    copy_to_clipboard(value, options, Content::ExpiringSecret)
 }
 
 /// Generates and returns a one-time password (OTP).
 ///
 /// This operation is synchronous and will block until decryption completes.
 pub fn generate_otp(file_path: &Path) -> Result<String, Error> {
-    // Decrypt file and find line starting with otpauth://
     let file_contents = decrypt_password_file(file_path)?;
-    let otpauth = file_contents
+    current_otp(&file_contents)
+}
+
+/// Generates the one-time password of the first `otpauth://` line of an entry.
+pub(super) fn current_otp(contents: &str) -> Result<String, Error> {
+    otp_of(contents)?
+        .generate_current()
+        .map_err(|e: time::SystemTimeError| Error::Pass(format!("failed to generate OTP: {}", e)))
+}
+
+fn otp_of(contents: &str) -> Result<TOTP, Error> {
+    let otpauth = contents
         .lines()
         .find(|line| line.starts_with("otpauth://"))
         .ok_or_else(|| Error::Pass("no OTP URL found".to_string()))?;
 
-    let totp = TOTP::from_url(otpauth)?;
-
-    totp.generate_current()
-        .map_err(|e: time::SystemTimeError| Error::Pass(format!("failed to generate OTP: {}", e)))
+    Ok(TOTP::from_url(otpauth)?)
 }
 
 /// Generates a one-time password (OTP) and copies it to the system clipboard.
 ///
+/// The code is cleared after the expiry of the options. With
+/// [`ClipboardOptions::refresh_otp`] it is also replaced at every period boundary
+/// until then.
+///
 /// This operation is synchronous and will block until decryption completes.
-pub fn copy_otp(file_path: &Path) -> Result<(), Error> {
-    let otp = generate_otp(file_path)?;
-    copy_to_clipboard(&otp, false)
+pub fn copy_otp(file_path: &Path, options: &ClipboardOptions) -> Result<(), Error> {
+    if !options.refresh_otp {
+        let otp = generate_otp(file_path)?;
+        return copy_to_clipboard(&otp, options, Content::ExpiringSecret);
+    }
+
+    let file_contents = decrypt_password_file(file_path)?;
+    let totp = otp_of(&file_contents)?;
+    copy_rotating(options, move || {
+        let otp_error =
+            |e: time::SystemTimeError| Error::Pass(format!("failed to generate OTP: {}", e));
+        let otp = totp.generate_current().map_err(otp_error)?;
+        // Codes change at the start of the next second after the time to live
+        let valid_for = Duration::from_secs(totp.ttl().map_err(otp_error)?);
+        Ok((otp, valid_for))
+    })
 }
@@ -0,0 +1,186 @@
+use std::{fmt, fs, path::Path};
+
+use crate::Error;
+
+/// Tag of a public-key encrypted session key packet.
+const TAG_PKESK: u8 = 1;
+/// Tag of a symmetric-key encrypted session key packet.
+const TAG_SKESK: u8 = 3;
+/// Tag of a marker packet, which is ignored.
+const TAG_MARKER: u8 = 10;
+
+/// Identifies the (sub)key an entry is encrypted for.
+///
+/// Version 3 session key packets contain the 8 byte key ID, version 6 packets the
+/// full fingerprint. A key ID of zeros stands for an anonymous recipient.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeyId(Vec<u8>);
+
+impl KeyId {
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Checks whether the recipient is hidden, like with `gpg --throw-keyids`.
+    pub fn is_wildcard(&self) -> bool {
+        self.0.iter().all(|byte| *byte == 0)
+    }
+
+    /// Checks whether this is the key ID or fingerprint of the key with the given
+    /// hex fingerprint.
+    pub fn matches_fingerprint(&self, fingerprint: &str) -> bool {
+        let hex = self.to_string();
+        let fingerprint = fingerprint.as_bytes();
+        fingerprint.len() >= hex.len()
+            && fingerprint[fingerprint.len() - hex.len()..].eq_ignore_ascii_case(hex.as_bytes())
+    }
+}
+
+impl fmt::Display for KeyId {
+    /// Formats the key ID as uppercase hex, like gpg displays long key IDs.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.iter().try_for_each(|byte| write!(f, "{byte:02X}"))
+    }
+}
+
+/// Lists the keys a password file is encrypted for without decrypting it.
+///
+/// Only the unencrypted session key packets at the start of the file are read, so
+/// neither the gpg-agent nor a pinentry is involved. ASCII armored files are
+/// supported as well.
+pub fn recipients_of(file_path: &Path) -> Result<Vec<KeyId>, Error> {
+    let contents = fs::read(file_path)?;
+    let binary = if contents.starts_with(b"-----BEGIN PGP MESSAGE-----") {
+        dearmor(&contents)?
+    } else {
+        contents
+    };
+    parse_recipients(&binary)
+}
+
+fn parse_recipients(mut data: &[u8]) -> Result<Vec<KeyId>, Error> {
+    let invalid = || Error::Pass("invalid OpenPGP message".to_string());
+    let mut recipients = Vec::new();
+    while let Some((&header, rest)) = data.split_first() {
+        if header & 0x80 == 0 {
+            return Err(invalid());
+        }
+        let (tag, length, rest) = if header & 0x40 != 0 {
+            let tag = header & 0x3f;
+            match rest {
+                [first @ 0..=191, rest @ ..] => (tag, *first as usize, rest),
+                [first @ 192..=223, second, rest @ ..] => {
+                    let length = ((*first as usize - 192) << 8) + *second as usize + 192;
+                    (tag, length, rest)
+                }
+                [255, a, b, c, d, rest @ ..] => {
+                    (tag, u32::from_be_bytes([*a, *b, *c, *d]) as usize, rest)
+                }
+                // Partial body lengths are only used for data packets
+                _ => break,
+            }
+        } else {
+            let tag = (header >> 2) & 0x0f;
+            match (header & 0x03, rest) {
+                (0, [length, rest @ ..]) => (tag, *length as usize, rest),
+                (1, [a, b, rest @ ..]) => (tag, u16::from_be_bytes([*a, *b]) as usize, rest),
+                (2, [a, b, c, d, rest @ ..]) => {
+                    (tag, u32::from_be_bytes([*a, *b, *c, *d]) as usize, rest)
+                }
+                _ => break,
+            }
+        };
+
+        match tag {
+            TAG_PKESK | TAG_SKESK | TAG_MARKER => {}
+            // The session key packets are followed by the encrypted data
+            _ => break,
+        }
+        let body = rest.get(..length).ok_or_else(invalid)?;
+        data = &rest[length..];
+
+        if tag == TAG_PKESK {
+            let key_id = match body {
+                [3, key_id @ ..] if key_id.len() >= 8 => &key_id[..8],
+                // Fingerprint is preceded by its length and the key version
+                [6, 0, ..] => &[0; 8],
+                [6, length, _, rest @ ..] if rest.len() + 1 >= *length as usize => {
+                    &rest[..*length as usize - 1]
+                }
+                _ => return Err(invalid()),
+            };
+            recipients.push(KeyId(key_id.to_vec()));
+        }
+    }
+    Ok(recipients)
+}
+
+/// Decodes the base64 body of an ASCII armored message.
+fn dearmor(armored: &[u8]) -> Result<Vec<u8>, Error> {
+    let invalid = || Error::Pass("invalid ASCII armor".to_string());
+    let text = std::str::from_utf8(armored).map_err(|_| invalid())?;
+    let mut lines = text.lines().map(str::trim).skip(1);
+    // Armor headers are separated from the body by an empty line
+    for line in lines.by_ref() {
+        if line.is_empty() {
+            break;
+        }
+    }
+
+    let mut bits = 0u32;
+    let mut bit_count = 0;
+    let mut binary = Vec::new();
+    for line in lines {
+        // The checksum and the footer follow the body
+        if line.starts_with('=') || line.starts_with("-----") {
+            break;
+        }
+        for c in line.bytes() {
+            let value = match c {
+                b'A'..=b'Z' => c - b'A',
+                b'a'..=b'z' => c - b'a' + 26,
+                b'0'..=b'9' => c - b'0' + 52,
+                b'+' => 62,
+                b'/' => 63,
+                b'=' => continue,
+                _ => return Err(invalid()),
+            };
+            bits = (bits << 6) | value as u32;
+            bit_count += 6;
+            if bit_count >= 8 {
+                bit_count -= 8;
+                binary.push((bits >> bit_count) as u8);
+            }
+        }
+    }
+    Ok(binary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_session_key_packets() {
+        let mut message = vec![
+            0xc1, 12, 3, 0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef, 18,
+        ];
+        message.extend([0, 0]);
+        // Old format header with anonymous recipient
+        message.extend([0x84, 10, 3, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+        // Encrypted data
+        message.extend([0xd2, 2, 1, 0]);
+
+        let recipients = parse_recipients(&message).unwrap();
+        assert_eq!(recipients.len(), 2);
+        assert_eq!(recipients[0].to_string(), "0123456789ABCDEF");
+        assert!(recipients[0].matches_fingerprint("AAAABBBBCCCCDDDDEEEE0123456789abcdef"));
+        assert!(recipients[1].is_wildcard());
+    }
+
+    #[test]
+    fn dearmor_message() {
+        let armored = b"-----BEGIN PGP MESSAGE-----\nComment: test\n\nwQwD\n=abcd\n-----END PGP MESSAGE-----\n";
+        assert_eq!(dearmor(armored).unwrap(), [0xc1, 0x0c, 0x03]);
+    }
+}
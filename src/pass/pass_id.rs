@@ -0,0 +1,160 @@
+use std::{
+    fmt,
+    ops::Deref,
+    path::{Component, Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::Error;
+
+/// A validated password ID like `web/example.org`.
+///
+/// IDs are paths relative to the store directory without the file extension. IDs
+/// that could point outside of the store, like `../x` or absolute paths, are
+/// rejected, so that IDs entered by users can be passed to the store safely.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PassId(String);
+
+impl PassId {
+    /// Validates an ID, trailing slashes are removed.
+    ///
+    /// Fails if the ID is empty or absolute, if it contains `.`, `..` or empty
+    /// components, or if it ends with `.gpg` like a file name. Other extensions of
+    /// entry files depend on the store, which rejects them as well.
+    pub fn new(id: impl Into<String>) -> Result<Self, Error> {
+        let id = id.into();
+        let invalid =
+            |reason: &str| Err(Error::Pass(format!("invalid password ID {id:?}: {reason}")));
+        if Path::new(&id).has_root() || Path::new(&id).is_absolute() {
+            return invalid("must be relative to the store");
+        }
+        let trimmed = id.trim_end_matches('/');
+        if trimmed.is_empty() {
+            return invalid("must not be empty");
+        }
+        if trimmed.contains('\0') {
+            return invalid("must not contain NUL characters");
+        }
+        if trimmed
+            .split(['/', std::path::MAIN_SEPARATOR])
+            .any(|component| matches!(component, "" | "." | ".."))
+        {
+            return invalid("must not contain empty, `.` or `..` components");
+        }
+        if trimmed.to_ascii_lowercase().ends_with(".gpg") {
+            return invalid("must not end with the extension .gpg");
+        }
+        Ok(Self(trimmed.to_string()))
+    }
+
+    /// Returns the ID of an entry file relative to the store directory, `None` if
+    /// the path is not a valid ID.
+    pub fn from_relative_path(path: &Path) -> Option<Self> {
+        if !path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            return None;
+        }
+        let id = path.with_extension("");
+        Self::new(id.to_str()?.replace(std::path::MAIN_SEPARATOR, "/")).ok()
+    }
+
+    /// Returns the path of the entry file with the extension relative to the store
+    /// directory.
+    pub fn to_relative_path(&self, extension: &str) -> PathBuf {
+        PathBuf::from(format!("{}.{extension}", self.0))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns the name of the entry without its folders.
+    pub fn name(&self) -> &str {
+        self.0.rsplit('/').next().unwrap_or_default()
+    }
+
+    /// Returns the folder containing the entry, `None` at the top level.
+    pub fn folder(&self) -> Option<&str> {
+        self.0.rsplit_once('/').map(|(folder, _)| folder)
+    }
+}
+
+impl Deref for PassId {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl AsRef<str> for PassId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for PassId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for PassId {
+    type Err = Error;
+
+    fn from_str(id: &str) -> Result<Self, Self::Err> {
+        Self::new(id)
+    }
+}
+
+impl TryFrom<String> for PassId {
+    type Error = Error;
+
+    fn try_from(id: String) -> Result<Self, Self::Error> {
+        Self::new(id)
+    }
+}
+
+impl TryFrom<&str> for PassId {
+    type Error = Error;
+
+    fn try_from(id: &str) -> Result<Self, Self::Error> {
+        Self::new(id)
+    }
+}
+
+impl From<PassId> for String {
+    fn from(id: PassId) -> Self {
+        id.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_paths_outside_of_the_store() {
+        for id in [
+            "",
+            "/etc/passwd",
+            "../x",
+            "web/../../x",
+            "web//x",
+            "./x",
+            "x.gpg",
+        ] {
+            assert!(PassId::new(id).is_err(), "{id:?} should be rejected");
+        }
+        let id = PassId::new("web/example.org/").unwrap();
+        assert_eq!(id.as_str(), "web/example.org");
+        assert_eq!(id.name(), "example.org");
+        assert_eq!(id.folder(), Some("web"));
+        assert_eq!(
+            PassId::from_relative_path(&id.to_relative_path("gpg")),
+            Some(id)
+        );
+    }
+}
@@ -0,0 +1,160 @@
+use gpgme::{Context, PassphraseRequest, PinentryMode};
+use std::{
+    cell::Cell,
+    fmt,
+    io::Write,
+    panic::AssertUnwindSafe,
+    sync::{Arc, Mutex},
+};
+
+use crate::Error;
+
+/// Callback that asks the user for the passphrase of a key, `None` cancels.
+///
+/// The provider may be called from several threads at once.
+pub type PassphraseProvider = Arc<dyn Fn(&KeyInfo) -> Option<SecretString> + Send + Sync>;
+
+/// Provider for loopback pinentry, `None` to use the pinentry of the gpg-agent.
+static PASSPHRASE_PROVIDER: Mutex<Option<PassphraseProvider>> = Mutex::new(None);
+
+thread_local! {
+    /// Whether the provider was asked for a passphrase on the current thread
+    static PASSPHRASE_REQUESTED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Information about the key whose passphrase is requested.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyInfo {
+    /// Key ID and user ID of the key, e.g. `"0123456789ABCDEF Alice <alice@example.org>"`
+    pub user_id_hint: Option<String>,
+    /// Additional information from GnuPG, like the IDs of the main key and subkey
+    pub description: Option<String>,
+    /// Whether the previously supplied passphrase was wrong
+    pub previous_attempt_failed: bool,
+}
+
+/// A passphrase that is overwritten when dropped and never printed.
+pub struct SecretString(String);
+
+impl SecretString {
+    pub fn new(secret: String) -> Self {
+        Self(secret)
+    }
+
+    pub fn expose_secret(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(secret: String) -> Self {
+        Self(secret)
+    }
+}
+
+impl fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretString(***)")
+    }
+}
+
+impl Drop for SecretString {
+    fn drop(&mut self) {
+        let mut bytes = std::mem::take(&mut self.0).into_bytes();
+        bytes.iter_mut().for_each(|byte| *byte = 0);
+        std::hint::black_box(&bytes);
+    }
+}
+
+/// Sets the provider that is asked for passphrases instead of the system pinentry.
+///
+/// With a provider, gpgme operations use [`PinentryMode::Loopback`], so that GUI
+/// frontends can show their own unlock dialog. This requires GnuPG 2.1 or later
+/// and, depending on its version, `allow-loopback-pinentry` in `gpg-agent.conf`.
+/// The provider applies to the whole process and may itself replace the provider.
+/// `None` restores the system pinentry.
+pub fn set_passphrase_provider(provider: Option<PassphraseProvider>) {
+    *PASSPHRASE_PROVIDER
+        .lock()
+        .unwrap_or_else(|e| e.into_inner()) = provider;
+}
+
+/// Checks whether a passphrase provider is set.
+pub(super) fn has_passphrase_provider() -> bool {
+    PASSPHRASE_PROVIDER
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .is_some()
+}
+
+/// Returns whether the provider was asked for a passphrase on the current thread
+/// since the last call.
+pub(super) fn take_passphrase_requested() -> bool {
+    PASSPHRASE_REQUESTED.replace(false)
+}
+
+/// Runs `f` with loopback pinentry if a passphrase provider is set.
+pub(super) fn with_passphrase<T>(
+    ctx: &mut Context,
+    f: impl FnOnce(&mut Context) -> Result<T, Error>,
+) -> Result<T, Error> {
+    // Not locked while the provider runs, so that it can replace itself
+    let provider = PASSPHRASE_PROVIDER
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    let Some(provider) = provider else {
+        return f(ctx);
+    };
+
+    let previous_mode = ctx.pinentry_mode();
+    ctx.set_pinentry_mode(PinentryMode::Loopback)?;
+    let provider = AssertUnwindSafe(provider);
+    let result = ctx.with_passphrase_provider(
+        move |request: PassphraseRequest<'_>, out: &mut dyn Write| {
+            PASSPHRASE_REQUESTED.set(true);
+            let info = KeyInfo {
+                user_id_hint: request.user_id_hint().ok().map(String::from),
+                description: request.description().ok().map(String::from),
+                previous_attempt_failed: request.prev_attempt_failed,
+            };
+            let passphrase = (*provider)(&info).ok_or(gpgme::Error::CANCELED)?;
+            out.write_all(passphrase.expose_secret().as_bytes())?;
+            out.write_all(b"\n")?;
+            Ok(())
+        },
+        f,
+    );
+    ctx.set_pinentry_mode(previous_mode)?;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secret_string_is_redacted() {
+        let secret = SecretString::from("hunter2".to_string());
+        assert_eq!(secret.expose_secret(), "hunter2");
+        assert!(!format!("{secret:?}").contains("hunter2"));
+    }
+
+    #[test]
+    fn provider_is_called_without_the_lock() {
+        let info = KeyInfo {
+            user_id_hint: None,
+            description: None,
+            previous_attempt_failed: false,
+        };
+        set_passphrase_provider(Some(Arc::new(|_| {
+            // Would deadlock if the provider was called with the lock held
+            let provided = has_passphrase_provider();
+            set_passphrase_provider(None);
+            provided.then(|| SecretString::from("hunter2".to_string()))
+        })));
+        let provider = PASSPHRASE_PROVIDER.lock().unwrap().clone().unwrap();
+        assert_eq!(provider(&info).unwrap().expose_secret(), "hunter2");
+        assert!(!has_passphrase_provider());
+    }
+}
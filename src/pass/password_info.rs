@@ -3,18 +3,57 @@ use icu::{
     datetime::{options::length, TypedDateTimeFormatter},
     locid::locale,
 };
-use std::{fs::Metadata, time::UNIX_EPOCH};
+use std::{fs::Metadata, path::PathBuf, time::UNIX_EPOCH};
 
 /// Stores the ID and metadata of a password file.
 #[derive(Debug, Clone)]
 pub struct PasswordInfo {
     pub id: String,
     pub metadata: Metadata,
+    /// Extension of the file without the dot, e.g. `gpg` or `age`
+    pub extension: String,
+    /// Path of the file relative to the directory of its store
+    ///
+    /// Unlike the ID, which is only meant for display, the path is kept as is if
+    /// it is not valid UTF-8.
+    pub path: PathBuf,
+    /// Score of how often and how recently the entry was used when it was read,
+    /// `0.0` if it was never used, see
+    /// [`PasswordStore::frecency`](crate::PasswordStore::frecency)
+    pub frecency: f64,
+    /// Name of the [added store](crate::PasswordStore::add_store) containing the
+    /// entry, `None` for the main store
+    pub store: Option<String>,
 }
 
 impl PasswordInfo {
+    /// Creates the info for a `.gpg` file.
     pub fn new(id: String, metadata: Metadata) -> Self {
-        PasswordInfo { id, metadata }
+        PasswordInfo {
+            path: PathBuf::from(format!("{id}.gpg")),
+            id,
+            metadata,
+            extension: "gpg".to_string(),
+            frecency: 0.0,
+            store: None,
+        }
+    }
+
+    pub fn with_extension(mut self, extension: impl Into<String>) -> Self {
+        self.extension = extension.into();
+        self.path.set_extension(&self.extension);
+        self
+    }
+
+    /// Sets the path of the file relative to the directory of its store.
+    pub fn with_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.path = path.into();
+        self
+    }
+
+    /// Returns the path of the file relative to the directory of its store.
+    pub fn relative_path(&self) -> PathBuf {
+        self.path.clone()
     }
 
     pub fn last_modified(&self) -> String {
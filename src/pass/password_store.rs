@@ -1,94 +1,1756 @@
 use std::{
-    env, fs, io,
+    collections::HashMap,
+    env, fs,
+    ops::Deref,
     path::{Path, PathBuf},
+    sync::{
+        mpsc::{self, Sender},
+        Arc, Mutex, MutexGuard, RwLock, Weak,
+    },
+    thread,
+    time::{Duration, Instant, SystemTime},
 };
+use totp_rs::TOTP;
 
-use super::PasswordInfo;
+use super::{
+    atomic,
+    cryptography::{self, Backend, CryptoBackend, Decryptor, SigningBackend, Verification},
+    fields, git,
+    history::History,
+    index::CachedIndex,
+    operations, permissions, recipients_of,
+    scanner::{is_hidden, strip_folder, Scanner, SymlinkPolicy},
+    sort::SortOrder,
+    timeout::{run_with_limits, CancellationHandle},
+    usage::Usage,
+    EntryTemplate, PassId, PasswordInfo,
+};
+use crate::{clipboard, Error, PasswordEvent};
+
+/// A recently modified entry, see [`PasswordStore::recently_modified`].
+#[derive(Debug, Clone)]
+pub struct RecentEntry {
+    pub id: String,
+    pub modified: SystemTime,
+}
+
+/// An immutable view of the password entries at one point in time.
+///
+/// Snapshots are cheap to create and clone, as they share the entries with the
+/// store until the store is modified. They dereference to a slice sorted by ID.
+#[derive(Debug, Clone, Default)]
+pub struct StoreSnapshot {
+    entries: Arc<Vec<PasswordInfo>>,
+}
+
+impl Deref for StoreSnapshot {
+    type Target = [PasswordInfo];
+
+    fn deref(&self) -> &Self::Target {
+        &self.entries
+    }
+}
+
+impl StoreSnapshot {
+    /// Returns the entry with the ID.
+    pub fn get(&self, pass_id: &str) -> Option<&PasswordInfo> {
+        self.binary_search_by(|info| info.id.as_str().cmp(pass_id))
+            .ok()
+            .map(|i| &self.entries[i])
+    }
+
+    /// Returns a reference to the entries that does not keep them from being
+    /// modified in place by the store.
+    pub(super) fn downgrade(&self) -> Weak<Vec<PasswordInfo>> {
+        Arc::downgrade(&self.entries)
+    }
+
+    /// Compares the entries of an earlier snapshot with this one.
+    pub fn diff_from(&self, earlier: &StoreSnapshot) -> StoreDiff {
+        StoreDiff::between(earlier, self)
+    }
+}
+
+/// Differences between two snapshots of a store, see [`PasswordStore::diff`].
+///
+/// The IDs are sorted.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StoreDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    /// Old and new ID of entries that were moved, detected by an identical
+    /// modification time and size
+    pub renamed: Vec<(String, String)>,
+    /// Entries whose modification time or size changed
+    pub touched: Vec<String>,
+}
+
+impl StoreDiff {
+    fn between(old: &[PasswordInfo], new: &[PasswordInfo]) -> Self {
+        let changes = StoreChanges::between(old, new);
+        let find = |entries: &[PasswordInfo], id: &str| {
+            entries
+                .binary_search_by(|info| info.id.as_str().cmp(id))
+                .map(|i| entries[i].clone())
+                .ok()
+        };
+        let mut added: Vec<PasswordInfo> = changes
+            .added
+            .iter()
+            .filter_map(|id| find(new, id))
+            .collect();
+
+        let mut diff = Self {
+            touched: changes.modified,
+            ..Default::default()
+        };
+        for id in changes.removed {
+            let renamed = find(old, &id).and_then(|info| {
+                added
+                    .iter()
+                    .position(|candidate| same_file(&info, candidate))
+            });
+            match renamed {
+                Some(i) => diff.renamed.push((id, added.remove(i).id)),
+                None => diff.removed.push(id),
+            }
+        }
+        diff.added = added.into_iter().map(|info| info.id).collect();
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.renamed.is_empty()
+            && self.touched.is_empty()
+    }
+}
+
+/// Entries that changed on disk, see [`PasswordStore::reload`].
+///
+/// The IDs are sorted.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StoreChanges {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    /// Entries whose modification time or size changed
+    pub modified: Vec<String>,
+}
+
+impl StoreChanges {
+    /// Compares two lists of entries sorted by ID.
+    fn between(old: &[PasswordInfo], new: &[PasswordInfo]) -> Self {
+        let mut changes = Self::default();
+        let (mut old, mut new) = (old.iter().peekable(), new.iter().peekable());
+        loop {
+            match (old.peek(), new.peek()) {
+                (Some(a), Some(b)) if a.id == b.id => {
+                    if !same_file(a, b) {
+                        changes.modified.push(b.id.clone());
+                    }
+                    old.next();
+                    new.next();
+                }
+                (Some(a), Some(b)) if a.id < b.id => {
+                    changes.removed.push(a.id.clone());
+                    old.next();
+                }
+                (Some(a), None) => {
+                    changes.removed.push(a.id.clone());
+                    old.next();
+                }
+                (_, Some(b)) => {
+                    changes.added.push(b.id.clone());
+                    new.next();
+                }
+                (None, None) => return changes,
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+fn same_file(a: &PasswordInfo, b: &PasswordInfo) -> bool {
+    a.extension == b.extension
+        && a.metadata.len() == b.metadata.len()
+        && a.metadata.modified().ok() == b.metadata.modified().ok()
+}
 
 /// A password store that manages password entries.
+///
+/// The store is `Send` and `Sync`, so that a single instance can be shared between
+/// UI and worker threads with an `Arc`. Configuration methods take `&mut self` and
+/// are meant to be called before sharing the store, operations take `&self`.
 #[derive(Default)]
 pub struct PasswordStore {
     pub store_dir: PathBuf,
-    pub passwords: Vec<PasswordInfo>,
+    passwords: RwLock<Arc<Vec<PasswordInfo>>>,
+    event_tx: Option<Sender<PasswordEvent>>,
+    auto_lock: Option<Duration>,
+    remove_to_trash: bool,
+    trash_retention: Option<Duration>,
+    /// Time of the last operation, `None` while the store is locked
+    last_activity: Mutex<Option<Instant>>,
+    history: Mutex<History>,
+    templates: HashMap<String, EntryTemplate>,
+    /// Extensions of entry files, `gpg` if empty
+    extensions: Vec<String>,
+    /// Decryptors by lowercase file extension
+    decryptors: HashMap<String, Arc<dyn Decryptor>>,
+    backend: Arc<Backend>,
+    signing_keys: Vec<String>,
+    decrypt_timeout: Option<Duration>,
+    /// Handle of the pending decryptions, replaced by [`PasswordStore::cancel_pending`]
+    cancellation: Mutex<CancellationHandle>,
+    /// Other stores by the ID prefix their entries are shown under
+    mounts: Vec<(String, PathBuf)>,
+    pub(super) symlinks: SymlinkPolicy,
+    pub(super) excludes: Vec<String>,
+    pub(super) sort_order: SortOrder,
+    /// Folder the entries are restricted to, empty for all entries
+    pub(super) prefix: String,
+    pub(super) max_depth: Option<usize>,
+    pub(super) index: CachedIndex,
+    pub(super) usage: Usage,
 }
 
 impl PasswordStore {
     /// Creates a new password store instance with loaded password entries.
     ///
     /// Initializes the store by reading all password entries from the password store
-    /// directory, sorting them by ID.
-    pub fn new() -> Self {
-        let store_dir = Self::get_store_dir();
+    /// directory, sorting them by ID. See [`builder`](Self::builder) for other
+    /// options.
+    ///
+    /// Fails if the store directory cannot be determined, see
+    /// [`get_store_dir`](Self::get_store_dir). If it does not exist or has no
+    /// `.gpg-id` file, [`Error::StoreNotInitialized`] is returned, so that frontends
+    /// can offer to [`init`](Self::init) the store.
+    pub fn new() -> Result<Self, Error> {
+        let store_dir = Self::get_store_dir()?;
+        if !Self::is_initialized(&store_dir) {
+            return Err(Error::StoreNotInitialized { path: store_dir });
+        }
+        Ok(Self::open(store_dir))
+    }
+
+    /// Checks whether the directory contains a store initialized with a `.gpg-id`
+    /// file, like [`init`](Self::init) creates it.
+    pub fn is_initialized(store_dir: &Path) -> bool {
+        store_dir.join(".gpg-id").is_file()
+    }
+
+    /// Creates a password store instance for the specified store directory.
+    pub fn open(store_dir: impl Into<PathBuf>) -> Self {
+        let store_dir = store_dir.into();
         let mut passwords = Self::get_password_infos(&store_dir);
         passwords.sort_by_key(|info| info.id.clone());
-        Self {
+        let store = Self {
             store_dir,
-            passwords,
+            ..Default::default()
+        };
+        store.apply_frecency(&mut passwords);
+        *store
+            .passwords
+            .write()
+            .expect("another thread holding the lock paniced") = Arc::new(passwords);
+        store
+    }
+
+    /// Initializes a password store for the given GPG IDs, like `pass init` does.
+    ///
+    /// Creates the store directory and writes the `.gpg-id` file. When
+    /// `PASSWORD_STORE_SIGNING_KEY` is set, the file is signed with these keys into
+    /// `.gpg-id.sig`. Existing entries are re-encrypted for the new recipients.
+    ///
+    /// This operation is synchronous and will block until re-encryption completes.
+    pub fn init(store_dir: impl AsRef<Path>, gpg_ids: &[&str]) -> Result<Self, Error> {
+        if gpg_ids.is_empty() {
+            return Err(Error::Pass("at least one GPG ID is required".to_string()));
+        }
+        let store_dir = store_dir.as_ref();
+        permissions::create_dir_all(store_dir)?;
+        let store = Self::open(store_dir);
+
+        let gpg_id_path = store_dir.join(".gpg-id");
+        let signature_path = store_dir.join(".gpg-id.sig");
+        let gpg_id_contents: String = gpg_ids.iter().map(|id| format!("{id}\n")).collect();
+        let signing_keys: Vec<String> = env::var("PASSWORD_STORE_SIGNING_KEY")
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(String::from)
+            .collect();
+
+        store.write_operation(
+            format!("Set GPG id to {}.", gpg_ids.join(", ")),
+            &[gpg_id_path.clone(), signature_path.clone()],
+            || {
+                atomic::write(&gpg_id_path, gpg_id_contents.as_bytes())?;
+                if !signing_keys.is_empty() {
+                    let signature =
+                        cryptography::sign_detached(gpg_id_contents.as_bytes(), &signing_keys)?;
+                    atomic::write(&signature_path, &signature)?;
+                } else if signature_path.exists() {
+                    // A signature of the previous contents would not be valid anymore
+                    fs::remove_file(&signature_path)?;
+                }
+                Ok(())
+            },
+        )?;
+
+        if !store.snapshot().is_empty() {
+            store.reencrypt("")?;
         }
+        Ok(store)
     }
 
-    /// Determines the password store directory path.
-    pub fn get_store_dir() -> PathBuf {
-        let home = dirs::home_dir().expect("could not determine home directory");
-        if let Some(store_path) = env::var_os("PASSWORD_STORE_DIR") {
-            let path = PathBuf::from(store_path);
-            if path.is_absolute() {
-                return path;
-            } else if let Ok(relative_to_home) = path
-                .strip_prefix("$HOME")
-                .or_else(|_| path.strip_prefix("~"))
+    /// Creates a new OpenPGP key for the user ID and initializes the store with it.
+    ///
+    /// Meant for first-time users without a key, see
+    /// [`generate_key`](crate::generate_key). The fingerprint of the new key is
+    /// written to `.gpg-id`.
+    pub fn init_with_new_key(store_dir: impl AsRef<Path>, user_id: &str) -> Result<Self, Error> {
+        let fingerprint = cryptography::generate_key(user_id, None)?;
+        Self::init(store_dir, &[fingerprint.as_str()])
+    }
+
+    /// Returns a snapshot of the password entries sorted by ID.
+    ///
+    /// UI threads can hold and iterate the snapshot while the store is refreshed or
+    /// modified by other threads, modifications are only visible in new snapshots.
+    pub fn snapshot(&self) -> StoreSnapshot {
+        StoreSnapshot {
+            entries: Arc::clone(
+                &self
+                    .passwords
+                    .read()
+                    .expect("another thread holding the lock paniced"),
+            ),
+        }
+    }
+
+    /// Returns the entry with the ID, see [`StoreSnapshot::get`].
+    pub fn get(&self, pass_id: &str) -> Option<PasswordInfo> {
+        self.snapshot().get(pass_id).cloned()
+    }
+
+    /// Returns the path of the entry file for the ID.
+    ///
+    /// The path is found in the store or the [added store](Self::add_store) of the
+    /// ID, with any of the [extensions](Self::set_extensions) of the store. For IDs
+    /// without an entry, the path a new entry would be created at is returned. The ID
+    /// is not validated, see [`PassId`].
+    pub fn resolve_path(&self, pass_id: &str) -> PathBuf {
+        self.entry_path(pass_id)
+    }
+
+    /// Rereads the entries from the store directory, e.g. after the store was
+    /// modified by pass or a git pull, and returns the changed entries.
+    ///
+    /// Unlike opening the store again, the configuration of the store is kept. The
+    /// entries are only replaced if they changed, existing snapshots keep the
+    /// previous entries.
+    pub fn reload(&self) -> StoreChanges {
+        let mut scanned = self.scan_stores();
+        scanned.sort_by(|a, b| a.id.cmp(&b.id));
+        self.apply_frecency(&mut scanned);
+        self.update_passwords(|passwords| {
+            let changes = StoreChanges::between(passwords, &scanned);
+            if !changes.is_empty() {
+                *passwords = scanned;
+            }
+            changes
+        })
+    }
+
+    /// Compares an earlier snapshot with the current entries, e.g. to summarize the
+    /// changes of a git pull after [`reload`](Self::reload).
+    pub fn diff(&self, earlier: &StoreSnapshot) -> StoreDiff {
+        self.snapshot().diff_from(earlier)
+    }
+
+    /// Applies a modification to the password entries.
+    ///
+    /// The entries are only copied if snapshots of them are still held.
+    pub(super) fn update_passwords<T>(&self, f: impl FnOnce(&mut Vec<PasswordInfo>) -> T) -> T {
+        let mut passwords = self
+            .passwords
+            .write()
+            .expect("another thread holding the lock paniced");
+        f(Arc::make_mut(&mut passwords))
+    }
+
+    pub(super) fn history(&self) -> MutexGuard<'_, History> {
+        self.history
+            .lock()
+            .expect("another thread holding the lock paniced")
+    }
+
+    /// Updates the entries for the given files after they were modified.
+    ///
+    /// Entries are added, updated or removed depending on whether the files exist.
+    pub(super) fn refresh_entries(&self, paths: &[PathBuf]) {
+        self.update_passwords(|passwords| {
+            for path in paths {
+                let Some(pass_id) = self.pass_id_for(path) else {
+                    continue;
+                };
+                let position = passwords.binary_search_by(|info| info.id.cmp(&pass_id));
+                let extension = path
+                    .extension()
+                    .map(|ext| ext.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                let (store, store_dir) = self.store_for_path(path);
+                let store = store.map(String::from);
+                let relative_path = path.strip_prefix(store_dir).unwrap_or(path);
+                let info = |metadata| {
+                    let mut info = PasswordInfo::new(pass_id.clone(), metadata)
+                        .with_extension(extension.clone())
+                        .with_path(relative_path);
+                    info.store = store.clone();
+                    info.frecency = self.frecency(&pass_id);
+                    info
+                };
+                match (position, path.metadata()) {
+                    (Ok(i), Ok(metadata)) => passwords[i] = info(metadata),
+                    (Err(i), Ok(metadata))
+                        if path.is_file() && self.in_scope(&pass_id, relative_path) =>
+                    {
+                        passwords.insert(i, info(metadata))
+                    }
+                    (Ok(i), Err(_)) => {
+                        passwords.remove(i);
+                    }
+                    _ => {}
+                }
+            }
+        });
+    }
+
+    /// Checks whether an entry is below the prefix and within the maximal depth the
+    /// store was opened with, see [`PasswordStoreBuilder`](crate::PasswordStoreBuilder).
+    fn in_scope(&self, pass_id: &str, relative_path: &Path) -> bool {
+        (self.prefix.is_empty() || strip_folder(pass_id, &self.prefix).is_some())
+            && self
+                .max_depth
+                .is_none_or(|max_depth| relative_path.components().count() <= max_depth + 1)
+    }
+
+    /// Sets how directories that are symbolic links are scanned and rereads the
+    /// entries, they are followed by default.
+    pub fn set_symlink_policy(&mut self, symlinks: SymlinkPolicy) {
+        self.symlinks = symlinks;
+        self.reload();
+    }
+
+    /// Sets patterns of entries and folders to hide from the entry list and rereads
+    /// the entries, e.g. `archive/`.
+    ///
+    /// The patterns use gitignore syntax and are applied after those in the
+    /// `.passepartout-ignore` file of the store directory, which is always read.
+    /// Hidden entries can still be accessed by their ID.
+    pub fn set_exclude_patterns(&mut self, patterns: Vec<String>) {
+        self.excludes = patterns;
+        self.reload();
+    }
+
+    /// Sets the channel used for sending events like [`PasswordEvent::Locked`].
+    pub fn set_event_sender(&mut self, event_tx: Sender<PasswordEvent>) {
+        self.event_tx = Some(event_tx);
+    }
+
+    /// Locks the store automatically after the specified duration without operations.
+    ///
+    /// The timeout is checked by [`check_auto_lock`](Self::check_auto_lock), which
+    /// should be called periodically, e.g. on every tick of the event loop.
+    pub fn set_auto_lock(&mut self, timeout: Option<Duration>) {
+        self.auto_lock = timeout;
+        self.touch();
+    }
+
+    /// Moves entries into the trash on [`remove`](Self::remove) instead of deleting them.
+    pub fn set_remove_to_trash(&mut self, enabled: bool) {
+        self.remove_to_trash = enabled;
+    }
+
+    /// Sets how long entries are kept in the trash before being deleted for good.
+    ///
+    /// Expired entries are removed whenever another entry is moved to the trash.
+    pub fn set_trash_retention(&mut self, retention: Option<Duration>) {
+        self.trash_retention = retention;
+    }
+
+    pub(super) fn trash_retention(&self) -> Option<Duration> {
+        self.trash_retention
+    }
+
+    /// Sets the file extensions of entries, e.g. `["gpg", "age"]`, and rereads the
+    /// entries from the store directory. Only `gpg` by default.
+    ///
+    /// New entries are always created as `.gpg` files, but entries with the other
+    /// extensions can be read, renamed and removed. Decrypting them requires a
+    /// [backend](Self::set_backend) that supports their format.
+    pub fn set_extensions(&mut self, extensions: Vec<String>) {
+        self.extensions = extensions;
+        self.reload();
+    }
+
+    /// Adds another store, e.g. of a team, whose entries are shown with the name
+    /// as prefix of their IDs, like `team/server`.
+    ///
+    /// The entries are tagged with the name in [`PasswordInfo::store`]. Operations
+    /// on them use the directory of the other store, so its `.gpg-id` files
+    /// determine the recipients. Entries of the main store below the prefix are
+    /// hidden. Aliases cannot point to entries of another store.
+    pub fn add_store(&mut self, name: &str, store_dir: impl Into<PathBuf>) {
+        self.mount(name, store_dir.into());
+        self.reload();
+    }
+
+    /// Adds another store without rereading the entries.
+    pub(super) fn mount(&mut self, name: &str, store_dir: PathBuf) {
+        let name = name.trim_matches('/').to_string();
+        self.mounts.retain(|(mounted, _)| *mounted != name);
+        self.mounts.push((name, store_dir));
+        // Nested prefixes are matched first
+        self.mounts.sort_by(|a, b| b.0.cmp(&a.0));
+    }
+
+    /// Returns the names and directories of the added stores, including gopass mounts.
+    pub fn stores(&self) -> Vec<(&str, &Path)> {
+        self.mounts
+            .iter()
+            .map(|(name, dir)| (name.as_str(), dir.as_path()))
+            .collect()
+    }
+
+    /// Returns the directory of the store containing the ID and the ID relative to it.
+    fn store_for_id<'a>(&'a self, pass_id: &'a str) -> (&'a Path, &'a str) {
+        for (name, dir) in &self.mounts {
+            if let Some(rest) = pass_id
+                .strip_prefix(name.as_str())
+                .and_then(|rest| rest.strip_prefix('/'))
             {
-                return home.join(relative_to_home);
-            };
+                return (dir, rest);
+            }
+        }
+        (&self.store_dir, pass_id)
+    }
+
+    /// Returns the name and directory of the store containing a path.
+    fn store_for_path(&self, path: &Path) -> (Option<&str>, &Path) {
+        self.mounts
+            .iter()
+            .filter(|(_, dir)| path.starts_with(dir))
+            .max_by_key(|(_, dir)| dir.components().count())
+            .map_or((None, self.store_dir.as_path()), |(name, dir)| {
+                (Some(name.as_str()), dir.as_path())
+            })
+    }
+
+    /// Returns the directory of the store containing a path, which is the boundary
+    /// for `.gpg-id` lookups.
+    pub(super) fn store_root(&self, path: &Path) -> &Path {
+        self.store_for_path(path).1
+    }
+
+    /// Registers a decryptor for entry files with the extension, replacing a
+    /// decryptor registered before.
+    ///
+    /// The extension is added to the [extensions](Self::set_extensions) of the store,
+    /// so that the entries show up in [`snapshot`](Self::snapshot). Their contents
+    /// are decrypted with the decryptor instead of the backend. Entries can only be
+    /// read, editing them fails since they cannot be encrypted again.
+    pub fn register_decryptor(&mut self, extension: &str, decryptor: Box<dyn Decryptor>) {
+        let extension = extension.to_ascii_lowercase();
+        let mut extensions: Vec<String> = self.extensions().into_iter().map(String::from).collect();
+        if !extensions.contains(&extension) {
+            extensions.push(extension.clone());
+        }
+        self.decryptors.insert(extension, Arc::from(decryptor));
+        self.set_extensions(extensions);
+    }
+
+    /// Returns the extensions of entry files.
+    pub fn extensions(&self) -> Vec<&str> {
+        if self.extensions.is_empty() {
+            vec![DEFAULT_EXTENSION]
+        } else {
+            self.extensions.iter().map(String::as_str).collect()
+        }
+    }
+
+    fn is_entry_extension(&self, path: &Path) -> bool {
+        path.extension().is_some_and(|ext| {
+            self.extensions()
+                .iter()
+                .any(|accepted| ext.eq_ignore_ascii_case(accepted))
+        })
+    }
+
+    /// Sets the crypto backend used to decrypt and encrypt entries, gpgme by default.
+    ///
+    /// The free functions like [`copy_password`](crate::copy_password) always use
+    /// gpgme.
+    pub fn set_backend(&mut self, backend: Backend) {
+        self.backend = Arc::new(backend);
+    }
+
+    /// Signs entries with the secret keys of the signers whenever they are written,
+    /// so that readers can detect entries changed by someone else with
+    /// [`fetch_verified`](Self::fetch_verified). Empty to stop signing, the default.
+    pub fn set_signing_keys(&mut self, signers: Vec<String>) {
+        self.signing_keys = signers;
+    }
+
+    /// Returns the backend for writing entries, which signs them if configured.
+    pub(super) fn backend(&self) -> SigningBackend<'_> {
+        SigningBackend {
+            backend: &self.backend,
+            signers: &self.signing_keys,
+        }
+    }
+
+    pub(super) fn uses_gpgme(&self) -> bool {
+        matches!(*self.backend, Backend::Gpgme)
+    }
+
+    /// Sets how long decrypting an entry may take before failing with
+    /// [`Error::Timeout`], no limit by default.
+    pub fn set_decrypt_timeout(&mut self, timeout: Option<Duration>) {
+        self.decrypt_timeout = timeout;
+    }
+
+    /// Cancels all pending decryptions, which return [`Error::Cancelled`].
+    ///
+    /// Operations started afterwards are not affected. Without a
+    /// [decrypt timeout](Self::set_decrypt_timeout) running decryptions cannot be
+    /// interrupted, only those that did not start yet are cancelled.
+    pub fn cancel_pending(&self) {
+        let handle = std::mem::take(
+            &mut *self
+                .cancellation
+                .lock()
+                .expect("another thread holding the lock paniced"),
+        );
+        handle.cancel();
+    }
+
+    /// Decrypts an entry file with the backend of the store.
+    ///
+    /// Sends a [`PasswordEvent::HardwareTouchRequired`] before decrypting with a key
+    /// on a hardware token, since the operation blocks until the user confirms it.
+    /// With a timeout decryption runs on a worker thread, so that it can time out or
+    /// be cancelled.
+    pub(super) fn read_entry(&self, path: &Path) -> Result<Vec<u8>, Error> {
+        let decryptor = self.decryptor_for(&self.resolve_alias(path));
+        let path = path.to_path_buf();
+        self.decrypt_with_limits(&path.clone(), move |backend| match decryptor {
+            Some(decryptor) => decryptor.decrypt(&fs::read(&path)?),
+            None => operations::read_entry(backend, &path),
+        })
+    }
+
+    /// Fails for entry files that are handled by a decryptor, since they cannot be
+    /// encrypted again.
+    pub(super) fn ensure_writable(&self, path: &Path) -> Result<(), Error> {
+        match self.decryptor_for(path) {
+            Some(_) => Err(Error::Pass(format!(
+                "{} cannot be modified, it is decrypted by a custom decryptor",
+                path.display()
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the decryptor registered for the extension of the file.
+    fn decryptor_for(&self, path: &Path) -> Option<Arc<dyn Decryptor>> {
+        let extension = path.extension()?.to_string_lossy().to_ascii_lowercase();
+        self.decryptors.get(&extension).cloned()
+    }
+
+    /// Runs a decryption of the entry file with the backend of the store on a worker
+    /// thread, see [`read_entry`](Self::read_entry).
+    fn decrypt_with_limits<T: Send + 'static>(
+        &self,
+        path: &Path,
+        decrypt: impl FnOnce(&Backend) -> Result<T, Error> + Send + 'static,
+    ) -> Result<T, Error> {
+        if self.event_tx.is_some() {
+            let uses_hardware_key = recipients_of(path)
+                .and_then(|recipients| self.backend.uses_hardware_key(&recipients))
+                .unwrap_or(false);
+            if let Some(pass_id) = self.pass_id_for(path).filter(|_| uses_hardware_key) {
+                self.send_event(PasswordEvent::HardwareTouchRequired { pass_id });
+            }
+        }
+        let backend = Arc::clone(&self.backend);
+        let cancellation = self
+            .cancellation
+            .lock()
+            .expect("another thread holding the lock paniced")
+            .clone();
+        run_with_limits(self.decrypt_timeout, &cancellation, move || {
+            decrypt(&backend)
+        })
+    }
+
+    /// Decrypts an entry file with the backend of the store, the contents must be
+    /// valid UTF-8.
+    pub(super) fn decrypt_file(&self, path: &Path) -> Result<String, Error> {
+        String::from_utf8(self.read_entry(path)?).map_err(|e| e.into())
+    }
+
+    /// Registers a template for new entries, replacing a template with the same name.
+    pub fn register_template(&mut self, template: EntryTemplate) {
+        self.templates.insert(template.name().to_string(), template);
+    }
+
+    /// Returns the registered template with the name.
+    pub fn template(&self, name: &str) -> Option<&EntryTemplate> {
+        self.templates.get(name)
+    }
+
+    /// Returns the registered templates sorted by name.
+    pub fn templates(&self) -> Vec<&EntryTemplate> {
+        let mut templates: Vec<&EntryTemplate> = self.templates.values().collect();
+        templates.sort_by_key(|template| template.name());
+        templates
+    }
+
+    /// Records activity, deferring the auto-lock.
+    ///
+    /// Operations of the store call this themselves, frontends should call it when
+    /// they use the free functions like [`copy_password`](crate::copy_password).
+    pub fn touch(&self) {
+        *self
+            .last_activity
+            .lock()
+            .expect("another thread holding the lock paniced") = Some(Instant::now());
+    }
+
+    /// Locks the store if the auto-lock timeout has elapsed, returns whether it did.
+    pub fn check_auto_lock(&self) -> bool {
+        let Some(timeout) = self.auto_lock else {
+            return false;
+        };
+        let idle = self
+            .last_activity
+            .lock()
+            .expect("another thread holding the lock paniced")
+            .is_some_and(|last_activity| last_activity.elapsed() >= timeout);
+        if idle {
+            self.lock();
+        }
+        idle
+    }
+
+    /// Locks the store.
+    ///
+    /// Clears secrets placed on the clipboard by this library, drops the GPG context
+    /// of the current thread, removes the passphrases of the store keys from the
+    /// gpg-agent cache and sends a [`PasswordEvent::Locked`].
+    pub fn lock(&self) {
+        *self
+            .last_activity
+            .lock()
+            .expect("another thread holding the lock paniced") = None;
+        clipboard::clear_now();
+        cryptography::reset_context();
+        if self.uses_gpgme() {
+            // Locking must not fail, e.g. when there is no gpg-agent running
+            let _ = self.clear_agent_cache();
+        }
+        self.send_event(PasswordEvent::Locked);
+    }
+
+    pub(super) fn event_sender(&self) -> Option<&Sender<PasswordEvent>> {
+        self.event_tx.as_ref()
+    }
+
+    pub(super) fn send_event(&self, event: PasswordEvent) {
+        if let Some(ref event_tx) = self.event_tx {
+            let _ = event_tx.send(event);
+        }
+    }
+
+    /// Determines the password store directory path.
+    ///
+    /// Returns `PASSWORD_STORE_DIR` or `~/.password-store`. Fails if the home
+    /// directory cannot be determined or if `PASSWORD_STORE_DIR` is neither absolute
+    /// nor starts with `~` or `$HOME`.
+    pub fn get_store_dir() -> Result<PathBuf, Error> {
+        let home_dir = || {
+            dirs::home_dir()
+                .ok_or_else(|| Error::Pass("could not determine home directory".to_string()))
+        };
+        let Some(store_path) = env::var_os("PASSWORD_STORE_DIR").filter(|path| !path.is_empty())
+        else {
+            return Ok(home_dir()?.join(".password-store"));
+        };
+        let path = PathBuf::from(store_path);
+        if path.is_absolute() {
+            Ok(path)
+        } else if let Ok(relative_to_home) = path
+            .strip_prefix("$HOME")
+            .or_else(|_| path.strip_prefix("~"))
+        {
+            Ok(home_dir()?.join(relative_to_home))
+        } else {
+            Err(Error::Pass(format!(
+                "PASSWORD_STORE_DIR is not an absolute path: {}",
+                path.display()
+            )))
         }
-        home.join(".password-store")
     }
 
     /// Collects and processes all password entries from the store directory.
     ///
     /// Recursively traverses the store directory to find all `.gpg` files and creates
-    /// [`PasswordInfo`] instances containing metadata for each entry.
+    /// [`PasswordInfo`] instances containing metadata for each entry. Hidden
+    /// directories like `.git` are skipped.
     pub fn get_password_infos(store_dir: &Path) -> Vec<PasswordInfo> {
-        Self::read_store_dir(store_dir)
-            .unwrap_or_default()
+        let scanner = Scanner {
+            store_dir: store_dir.to_path_buf(),
+            mounts: Vec::new(),
+            extensions: vec![DEFAULT_EXTENSION.to_string()],
+            symlinks: SymlinkPolicy::default(),
+            excludes: Vec::new(),
+            prefix: String::new(),
+            max_depth: None,
+            event_tx: None,
+        };
+        scanner.collect_dir(store_dir)
+    }
+
+    /// Collects the entries of the store and of the added stores.
+    fn scan_stores(&self) -> Vec<PasswordInfo> {
+        self.scanner().collect()
+    }
+
+    /// Collects the entries below `dir` with the extensions of the store.
+    pub(super) fn scan_store_dir(&self, dir: &Path) -> Vec<PasswordInfo> {
+        self.scanner().collect_dir(dir)
+    }
+
+    fn scanner(&self) -> Scanner {
+        Scanner {
+            store_dir: self.store_dir.clone(),
+            mounts: self.mounts.clone(),
+            extensions: self.extensions().into_iter().map(String::from).collect(),
+            symlinks: self.symlinks,
+            excludes: self.excludes.clone(),
+            prefix: self.prefix.clone(),
+            max_depth: self.max_depth,
+            event_tx: self.event_tx.clone(),
+        }
+    }
+
+    /// Returns the entries of the store while they are found, unsorted.
+    ///
+    /// The store directory is scanned again in a background thread, so that the
+    /// first entries of huge stores can be shown before the scan is complete. The
+    /// scan stops when the iterator is dropped. Entries of the store are not updated,
+    /// see [`reload`](Self::reload).
+    pub fn entries(&self) -> impl Iterator<Item = PasswordInfo> {
+        let scanner = self.scanner();
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || scanner.scan(|info| tx.send(info).is_ok()));
+        rx.into_iter()
+    }
+
+    /// Creates an alias that makes an existing entry available under another ID.
+    ///
+    /// The alias is stored as a relative symbolic link next to the other entries,
+    /// so it is followed transparently when the entry is decrypted and stays valid
+    /// when the whole store is moved or cloned.
+    pub fn alias(&self, existing_id: &str, alias_id: &str) -> Result<(), Error> {
+        self.touch();
+        let target = self.checked_path(existing_id)?;
+        if !target.is_file() {
+            return Err(Error::Pass(format!("no entry found for {existing_id}")));
+        }
+        let link = self.with_same_extension(alias_id, &target)?;
+        if link.symlink_metadata().is_ok() {
+            return Err(Error::Pass(format!(
+                "an entry already exists for {alias_id}"
+            )));
+        }
+
+        self.write_operation(
+            format!("Add alias {alias_id} for {existing_id}."),
+            std::slice::from_ref(&link),
+            || self.link_entry(&target, &link),
+        )
+    }
+
+    /// Creates a symbolic link to an entry, relative to the directory of the link.
+    fn link_entry(&self, target: &Path, link: &Path) -> Result<(), Error> {
+        let link_dir = link.parent().expect("entry path should have a parent");
+        let store_dir = self.store_root(link);
+        let target = target.strip_prefix(store_dir).map_err(|_| {
+            Error::Pass("aliases cannot point to entries of another store".to_string())
+        })?;
+        let depth = link_dir
+            .strip_prefix(store_dir)
+            .expect("store_dir should be a prefix")
+            .components()
+            .count();
+        let mut relative_target = PathBuf::new();
+        for _ in 0..depth {
+            relative_target.push("..");
+        }
+        relative_target.push(target);
+
+        permissions::create_dir_all(link_dir)?;
+        Self::symlink(&relative_target, link)
+    }
+
+    /// Renames an entry, like `pass mv` does.
+    ///
+    /// Intermediate directories are created and directories left empty are removed.
+    /// If the recipients of the destination differ, the entry is re-encrypted for
+    /// them. Aliases of the entry and its display metadata are updated as well.
+    /// Sends a [`PasswordEvent::EntryRenamed`] with the updated entry.
+    ///
+    /// This operation is synchronous and will block until re-encryption completes.
+    pub fn rename(&self, old_id: &str, new_id: &str) -> Result<(), Error> {
+        self.touch();
+        let old_path = self.checked_path(old_id)?;
+        let new_path = self.with_same_extension(new_id, &old_path)?;
+        if old_path.symlink_metadata().is_err() {
+            return Err(Error::Pass(format!("no entry found for {old_id}")));
+        }
+        if new_path.symlink_metadata().is_ok() {
+            return Err(Error::Pass(format!("an entry already exists for {new_id}")));
+        }
+
+        // Aliases pointing to the entry need to be linked again
+        let aliases: Vec<PathBuf> = self
+            .snapshot()
             .iter()
-            .filter_map(|path| {
-                // Get pass ID
-                let pass_id = path
-                    .strip_prefix(store_dir)
-                    .expect("store_dir should be a prefix")
-                    .with_extension("")
-                    .to_string_lossy()
-                    .into();
-
-                match path.metadata() {
-                    Ok(metadata) => Some(PasswordInfo::new(pass_id, metadata)),
-                    Err(_) => None,
+            .map(|info| self.entry_path(&info.id))
+            .filter(|path| path != &old_path && self.resolve_alias(path) == old_path)
+            .collect();
+        let mut paths = vec![old_path.clone(), new_path.clone()];
+        paths.extend(aliases.iter().cloned());
+        // Part of the same operation, so that a single undo reverts both
+        let moves_metadata = self.has_display_metadata(old_id)?;
+        if moves_metadata {
+            paths.push(self.metadata_path());
+        }
+
+        let is_alias = old_path.is_symlink();
+        let reencrypt = !is_alias && self.gpg_ids_for(old_id).ok() != self.gpg_ids_for(new_id).ok();
+        self.write_operation(format!("Rename {old_id} to {new_id}."), &paths, || {
+            if is_alias {
+                self.link_entry(&self.resolve_alias(&old_path), &new_path)?;
+                fs::remove_file(&old_path)?;
+            } else if reencrypt {
+                let contents = self.read_entry(&old_path)?;
+                let recipients = self.gpg_ids_for(new_id)?;
+                operations::write_new_entry(&self.backend(), &new_path, &contents, &recipients)?;
+                fs::remove_file(&old_path)?;
+            } else {
+                permissions::create_dir_all(
+                    new_path.parent().expect("entry path should have a parent"),
+                )?;
+                fs::rename(&old_path, &new_path)?;
+            }
+            for alias in &aliases {
+                fs::remove_file(alias)?;
+                self.link_entry(&new_path, alias)?;
+            }
+            if let Some(parent) = old_path.parent() {
+                self.prune_empty_dirs(parent);
+            }
+            if moves_metadata {
+                self.move_display_metadata(old_id, new_id)?;
+            }
+            Ok(())
+        })?;
+        self.rename_usage(old_id, new_id)?;
+
+        if let Some(info) = self.get(new_id) {
+            self.send_event(PasswordEvent::EntryRenamed {
+                old_id: old_id.to_string(),
+                info: Box::new(info),
+            });
+        }
+        Ok(())
+    }
+
+    /// Copies an entry to a new ID, like `pass cp` does.
+    ///
+    /// If the recipients of the destination differ, the copy is re-encrypted for
+    /// them. Copying an alias copies the entry it points to.
+    ///
+    /// This operation is synchronous and will block until re-encryption completes.
+    pub fn copy_entry(&self, src_id: &str, dst_id: &str) -> Result<(), Error> {
+        self.touch();
+        let src_path = self.resolve_alias(&self.checked_path(src_id)?);
+        let dst_path = self.with_same_extension(dst_id, &src_path)?;
+        if !src_path.is_file() {
+            return Err(Error::Pass(format!("no entry found for {src_id}")));
+        }
+        if dst_path.symlink_metadata().is_ok() {
+            return Err(Error::Pass(format!("an entry already exists for {dst_id}")));
+        }
+
+        let src_recipients = self.gpg_ids_for(src_id).ok();
+        let dst_recipients = self.gpg_ids_for(dst_id).ok();
+        self.write_operation(
+            format!("Copy {src_id} to {dst_id}."),
+            std::slice::from_ref(&dst_path),
+            || {
+                if src_recipients != dst_recipients {
+                    let contents = self.read_entry(&src_path)?;
+                    let recipients = self.gpg_ids_for(dst_id)?;
+                    operations::write_new_entry(&self.backend(), &dst_path, &contents, &recipients)
+                } else {
+                    atomic::write_new(&dst_path, &fs::read(&src_path)?)?;
+                    Ok(())
                 }
-            })
-            .collect()
+            },
+        )
+    }
+
+    /// Creates a new encrypted entry containing only a password.
+    ///
+    /// The password is encrypted for the recipients from the nearest `.gpg-id` file,
+    /// like `pass insert` does. Fails if the password contains a line break or an
+    /// entry with the ID already exists, see [`insert_multiline`](Self::insert_multiline)
+    /// for entries with additional lines.
+    ///
+    /// This operation is synchronous and will block until encryption completes.
+    pub fn insert(&self, pass_id: &str, password: &str) -> Result<(), Error> {
+        if password.contains('\n') {
+            return Err(Error::Pass(
+                "password must not contain line breaks".to_string(),
+            ));
+        }
+        self.insert_contents(pass_id, format!("{password}\n").as_bytes())
     }
 
-    fn read_store_dir(dir: &Path) -> io::Result<Vec<PathBuf>> {
-        let mut result = Vec::new();
-
-        fn visit_dir(dir: &Path, result: &mut Vec<PathBuf>) -> io::Result<()> {
-            for entry in fs::read_dir(dir)? {
-                let path = entry?.path();
-                if path.is_dir() {
-                    visit_dir(&path, result)?;
-                } else if path.is_file()
-                    && path
-                        .extension()
-                        .is_some_and(|ext| ext.eq_ignore_ascii_case("gpg"))
-                {
-                    result.push(path);
+    /// Creates a new encrypted entry with arbitrary contents, like `pass insert -m` does.
+    ///
+    /// The first line of the contents is the password, the contents can be assembled
+    /// with an [`EntryBuilder`](crate::EntryBuilder). Fails if an entry with the ID
+    /// already exists.
+    ///
+    /// This operation is synchronous and will block until encryption completes.
+    pub fn insert_multiline(&self, pass_id: &str, contents: &str) -> Result<(), Error> {
+        self.insert_contents(pass_id, contents.as_bytes())
+    }
+
+    /// Creates a new encrypted entry holding arbitrary binary data.
+    ///
+    /// Binary entries like SSH keys or PDF files can be read back with
+    /// [`fetch_binary`](Self::fetch_binary). Fails if an entry with the ID already
+    /// exists.
+    ///
+    /// This operation is synchronous and will block until encryption completes.
+    pub fn insert_binary(&self, pass_id: &str, contents: &[u8]) -> Result<(), Error> {
+        self.insert_contents(pass_id, contents)
+    }
+
+    /// Retrieves the decrypted contents of an entry without requiring valid UTF-8.
+    ///
+    /// This operation is synchronous and will block until decryption completes.
+    pub fn fetch_binary(&self, pass_id: &str) -> Result<Vec<u8>, Error> {
+        self.touch();
+        let contents = self.read_entry(&self.checked_path(pass_id)?)?;
+        self.record_use(pass_id);
+        Ok(contents)
+    }
+
+    /// Retrieves the contents of an entry and checks whether it carries a valid
+    /// signature, see [`set_signing_keys`](Self::set_signing_keys).
+    ///
+    /// This operation is synchronous and will block until decryption completes.
+    pub fn fetch_verified(&self, pass_id: &str) -> Result<(String, Verification), Error> {
+        self.touch();
+        let path = self.checked_path(pass_id)?;
+        let (plain, verification) = self.decrypt_with_limits(&path.clone(), move |backend| {
+            backend.decrypt_and_verify(&fs::read(&path)?)
+        })?;
+        self.record_use(pass_id);
+        Ok((String::from_utf8(plain)?, verification))
+    }
+
+    fn insert_contents(&self, pass_id: &str, contents: &[u8]) -> Result<(), Error> {
+        self.touch();
+        let path = self.checked_path(pass_id)?;
+        if path.symlink_metadata().is_ok() {
+            return Err(Error::Pass(format!(
+                "an entry already exists for {pass_id}"
+            )));
+        }
+        let recipients = self.gpg_ids_for(pass_id)?;
+        self.write_operation(
+            format!("Add given password for {pass_id} to store."),
+            std::slice::from_ref(&path),
+            || operations::write_new_entry(&self.backend(), &path, contents, &recipients),
+        )
+    }
+
+    /// Edits an entry in memory and re-encrypts it.
+    ///
+    /// The decrypted contents are passed to `edit`, the changed contents are encrypted
+    /// for the recipients from the nearest `.gpg-id` file and replace the entry
+    /// atomically. The entry is left untouched if the contents did not change.
+    ///
+    /// This operation is synchronous and will block until decryption and encryption
+    /// complete.
+    pub fn edit_entry<F>(&self, pass_id: &str, edit: F) -> Result<(), Error>
+    where
+        F: FnOnce(&mut String),
+    {
+        self.modify_entry(
+            pass_id,
+            format!("Edit password for {pass_id} using passepartout."),
+            |contents| {
+                edit(contents);
+                Ok(())
+            },
+        )
+    }
+
+    /// Appends an `otpauth://` URI to an entry, like `pass otp append` does.
+    ///
+    /// The URI is validated before it is stored. Fails if the entry already contains
+    /// an OTP URI.
+    ///
+    /// This operation is synchronous and will block until decryption and encryption
+    /// complete.
+    pub fn append_otp(&self, pass_id: &str, otpauth: &str) -> Result<(), Error> {
+        let otpauth = otpauth.trim();
+        if !otpauth.starts_with("otpauth://") {
+            return Err(Error::Pass("invalid OTP URL".to_string()));
+        }
+        TOTP::from_url_unchecked(otpauth)?;
+
+        self.modify_entry(
+            pass_id,
+            format!("Add OTP secret for {pass_id} to store."),
+            |contents| {
+                if contents.lines().any(|line| line.starts_with("otpauth://")) {
+                    return Err(Error::Pass(format!(
+                        "an OTP URL already exists for {pass_id}"
+                    )));
+                }
+                if !contents.is_empty() && !contents.ends_with('\n') {
+                    contents.push('\n');
                 }
+                contents.push_str(otpauth);
+                contents.push('\n');
+                Ok(())
+            },
+        )
+    }
+
+    /// Sets the value of a `key: value` field of an entry.
+    ///
+    /// An existing field with the key (ignoring case) is replaced in place, otherwise
+    /// the field is appended. The password on the first line and the order of the
+    /// other lines are preserved.
+    ///
+    /// This operation is synchronous and will block until decryption and encryption
+    /// complete.
+    pub fn set_field(&self, pass_id: &str, key: &str, value: &str) -> Result<(), Error> {
+        if key.is_empty() || key.contains(|c: char| c == ':' || c.is_whitespace()) {
+            return Err(Error::Pass(format!("invalid field name: {key}")));
+        }
+        if value.contains('\n') {
+            return Err(Error::Pass(
+                "field values must be a single line".to_string(),
+            ));
+        }
+
+        self.modify_entry(
+            pass_id,
+            format!("Update {key} for {pass_id}."),
+            |contents| {
+                *contents = fields::set_field(contents, key, value);
+                Ok(())
+            },
+        )
+    }
+
+    /// Decrypts an entry, applies `modify` and re-encrypts it if it changed.
+    fn modify_entry<F>(&self, pass_id: &str, description: String, modify: F) -> Result<(), Error>
+    where
+        F: FnOnce(&mut String) -> Result<(), Error>,
+    {
+        self.touch();
+        // Replace the file an alias points to rather than the alias itself
+        let path = self.resolve_alias(&self.checked_path(pass_id)?);
+        self.ensure_writable(&path)?;
+        let mut contents = self.decrypt_file(&path)?;
+        let original = contents.clone();
+        modify(&mut contents)?;
+        if contents == original {
+            return Ok(());
+        }
+
+        let recipients = self.gpg_ids_for(pass_id)?;
+        self.write_operation(description, std::slice::from_ref(&path), || {
+            operations::write_entry(&self.backend(), &path, contents.as_bytes(), &recipients)
+        })
+    }
+
+    /// Removes an entry, like `pass rm` does.
+    ///
+    /// Directories left empty are removed as well. The entry is moved into the trash
+    /// instead if enabled with [`set_remove_to_trash`](Self::set_remove_to_trash).
+    pub fn remove(&self, pass_id: &str) -> Result<(), Error> {
+        self.remove_with_confirmation(pass_id, |_| true).map(|_| ())
+    }
+
+    /// Removes an entry after `confirm` returned `true` for it.
+    ///
+    /// Returns whether the entry was removed.
+    pub fn remove_with_confirmation<F>(&self, pass_id: &str, confirm: F) -> Result<bool, Error>
+    where
+        F: FnOnce(&PasswordInfo) -> bool,
+    {
+        self.touch();
+        let snapshot = self.snapshot();
+        let info = snapshot
+            .get(pass_id)
+            .ok_or_else(|| Error::Pass(format!("no entry found for {pass_id}")))?;
+        if !confirm(info) {
+            return Ok(false);
+        }
+
+        if self.remove_to_trash {
+            self.trash(pass_id)?;
+        } else {
+            let path = self.entry_path(pass_id);
+            self.write_operation(
+                format!("Remove {pass_id} from store."),
+                std::slice::from_ref(&path),
+                || {
+                    fs::remove_file(&path)?;
+                    if let Some(parent) = path.parent() {
+                        self.prune_empty_dirs(parent);
+                    }
+                    Ok(())
+                },
+            )?;
+        }
+        Ok(true)
+    }
+
+    /// Returns the GPG IDs an entry is encrypted for, like pass determines them.
+    ///
+    /// Walks up from the directory of the entry to the store directory and reads the
+    /// nearest `.gpg-id` file. A line may contain multiple IDs separated by
+    /// whitespace, lines starting with `#` are comments. Fails if no file is found
+    /// or the nearest one lists no IDs.
+    ///
+    /// When `PASSWORD_STORE_SIGNING_KEY` is set, the file must have a valid signature
+    /// `.gpg-id.sig` by one of these keys like pass requires, otherwise
+    /// [`Error::SignatureVerification`] is returned.
+    pub fn gpg_ids_for(&self, pass_id: &str) -> Result<Vec<String>, Error> {
+        let path = self.checked_path(pass_id)?;
+        let store_dir = self.store_root(&path);
+        for dir in path.ancestors().skip(1) {
+            if !dir.starts_with(store_dir) {
+                break;
             }
+            let gpg_id = dir.join(".gpg-id");
+            if gpg_id.is_file() {
+                let recipients = self.read_gpg_id(&gpg_id)?;
+                if recipients.is_empty() {
+                    break;
+                }
+                return Ok(recipients);
+            }
+        }
+        Err(Error::Pass(format!("no recipients found for {pass_id}")))
+    }
+
+    /// Reads the IDs of the `.gpg-id` file in the store directory, which apply to
+    /// entries without a `.gpg-id` file in their folders.
+    pub(super) fn root_gpg_ids(&self) -> Result<Vec<String>, Error> {
+        let gpg_id = self.store_dir.join(".gpg-id");
+        let recipients = if gpg_id.is_file() {
+            self.read_gpg_id(&gpg_id)?
+        } else {
+            Vec::new()
+        };
+        if recipients.is_empty() {
+            return Err(Error::Pass(format!(
+                "no recipients found in {}",
+                gpg_id.display()
+            )));
+        }
+        Ok(recipients)
+    }
+
+    /// Reads the IDs of a `.gpg-id` file after verifying its signature.
+    fn read_gpg_id(&self, gpg_id: &Path) -> Result<Vec<String>, Error> {
+        let contents = fs::read(gpg_id)?;
+        self.verify_gpg_id(gpg_id, &contents)?;
+        Ok(String::from_utf8(contents)?
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.starts_with('#'))
+            .flat_map(str::split_whitespace)
+            .map(String::from)
+            .collect())
+    }
+
+    fn verify_gpg_id(&self, gpg_id: &Path, contents: &[u8]) -> Result<(), Error> {
+        let signing_keys = env::var("PASSWORD_STORE_SIGNING_KEY").unwrap_or_default();
+        if signing_keys.trim().is_empty() {
+            return Ok(());
+        }
+        let invalid = || Error::SignatureVerification(gpg_id.to_path_buf());
+        let signature = fs::read(gpg_id.with_file_name(".gpg-id.sig")).map_err(|_| invalid())?;
+        let signers = self.backend.verify_detached(contents, &signature)?;
+        let trusted = signing_keys.split_whitespace().any(|key| {
+            signers
+                .iter()
+                .any(|fingerprint| fingerprint.eq_ignore_ascii_case(key))
+        });
+        if trusted {
             Ok(())
+        } else {
+            Err(invalid())
+        }
+    }
+
+    /// Returns up to `limit` entries ordered from most to least recently modified.
+    ///
+    /// When the store is a git repository, the time of the last commit touching an
+    /// entry is used, so that the order survives fresh clones where every file has
+    /// the same modification time. Otherwise the file modification time is used.
+    pub fn recently_modified(&self, limit: usize) -> Vec<RecentEntry> {
+        self.touch();
+        let mut commit_times = HashMap::new();
+        let store_dirs =
+            std::iter::once(&self.store_dir).chain(self.mounts.iter().map(|(_, dir)| dir));
+        for store_dir in store_dirs.filter(|dir| git::is_repository(dir)) {
+            let times = git::last_commit_times(store_dir).unwrap_or_default();
+            commit_times.extend(
+                times
+                    .into_iter()
+                    .map(|(path, time)| (store_dir.join(path), time)),
+            );
+        }
+
+        let mut entries: Vec<RecentEntry> = self
+            .snapshot()
+            .iter()
+            .filter_map(|info| {
+                let (store_dir, pass_id) = self.store_for_id(&info.id);
+                let path = store_dir.join(format!("{pass_id}.{}", info.extension));
+                let modified = commit_times
+                    .get(&path)
+                    .copied()
+                    .or_else(|| info.metadata.modified().ok())?;
+                Some(RecentEntry {
+                    id: info.id.clone(),
+                    modified,
+                })
+            })
+            .collect();
+        entries.sort_by(|a, b| b.modified.cmp(&a.modified).then_with(|| a.id.cmp(&b.id)));
+        entries.truncate(limit);
+        entries
+    }
+
+    /// Returns the path of the entry, a `.gpg` file unless an entry with another of
+    /// the [extensions](Self::set_extensions) exists.
+    pub(crate) fn entry_path(&self, pass_id: &str) -> PathBuf {
+        let (store_dir, relative_id) = self.store_for_id(pass_id);
+        // Use the scanned path, the ID may not be the exact file name
+        if let Some(info) = self.snapshot().get(pass_id) {
+            let path = store_dir.join(&info.path);
+            if path.symlink_metadata().is_ok() {
+                return path;
+            }
+        }
+        let pass_id = relative_id;
+        let path = store_dir.join(format!("{pass_id}.{DEFAULT_EXTENSION}"));
+        if self.extensions.is_empty() || path.symlink_metadata().is_ok() {
+            return path;
+        }
+        self.extensions()
+            .into_iter()
+            .map(|ext| store_dir.join(format!("{pass_id}.{ext}")))
+            .find(|path| path.symlink_metadata().is_ok())
+            .unwrap_or(path)
+    }
+
+    /// Returns the path of the entry file for an ID given by the user, failing if
+    /// it is not a valid [`PassId`].
+    pub(super) fn checked_path(&self, pass_id: &str) -> Result<PathBuf, Error> {
+        self.check_id(pass_id)?;
+        Ok(self.entry_path(pass_id))
+    }
+
+    /// Fails if the ID is not a valid [`PassId`] or ends with one of the configured
+    /// extensions, like a file name of the store.
+    fn check_id(&self, pass_id: &str) -> Result<(), Error> {
+        let id = PassId::new(pass_id)?;
+        let lowercase = id.to_ascii_lowercase();
+        if let Some(extension) = self
+            .extensions()
+            .into_iter()
+            .find(|extension| lowercase.ends_with(&format!(".{}", extension.to_ascii_lowercase())))
+        {
+            return Err(Error::Pass(format!(
+                "invalid password ID {pass_id:?}: must not end with the extension .{extension}"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Returns the path for the ID with the extension of another entry file, failing
+    /// if it is not a valid [`PassId`].
+    fn with_same_extension(&self, pass_id: &str, other: &Path) -> Result<PathBuf, Error> {
+        self.check_id(pass_id)?;
+        let (dir, pass_id) = self.store_for_id(pass_id);
+        let extension = other.extension().unwrap_or_default().to_string_lossy();
+        Ok(dir.join(format!("{pass_id}.{extension}")))
+    }
+
+    /// Returns the path of the entry an alias points to, other paths are returned as is.
+    pub(super) fn resolve_alias(&self, path: &Path) -> PathBuf {
+        let Ok(target) = fs::read_link(path) else {
+            return path.to_path_buf();
+        };
+        let mut resolved = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        for component in target.components() {
+            match component {
+                std::path::Component::ParentDir => {
+                    resolved.pop();
+                }
+                std::path::Component::CurDir => {}
+                component => resolved.push(component),
+            }
+        }
+        resolved
+    }
+
+    /// Returns the pass ID for an entry file in the store.
+    fn pass_id_for(&self, path: &Path) -> Option<String> {
+        if !self.is_entry_extension(path) {
+            return None;
+        }
+        let (store, store_dir) = self.store_for_path(path);
+        let relative_path = path.strip_prefix(store_dir).ok()?;
+        if relative_path.parent().is_some_and(|dir| {
+            dir.components()
+                .any(|component| is_hidden(Path::new(component.as_os_str())))
+        }) {
+            return None;
+        }
+        let pass_id = relative_path
+            .with_extension("")
+            .to_string_lossy()
+            .into_owned();
+        Some(match store {
+            Some(name) => format!("{name}/{pass_id}"),
+            None => pass_id,
+        })
+    }
+
+    /// Removes empty directories from `dir` upwards, stopping at the store directory.
+    pub(super) fn prune_empty_dirs(&self, dir: &Path) {
+        let store_dir = self.store_root(dir);
+        let mut dir = Some(dir);
+        while let Some(current) = dir {
+            if current == store_dir || !current.starts_with(store_dir) {
+                break;
+            }
+            // Fails for directories that are not empty
+            if fs::remove_dir(current).is_err() {
+                break;
+            }
+            dir = current.parent();
+        }
+    }
+
+    #[cfg(unix)]
+    pub(super) fn symlink(target: &Path, link: &Path) -> Result<(), Error> {
+        std::os::unix::fs::symlink(target, link).map_err(|e| e.into())
+    }
+
+    #[cfg(not(unix))]
+    pub(super) fn symlink(_target: &Path, _link: &Path) -> Result<(), Error> {
+        Err(Error::Pass(
+            "aliases are only supported on Unix platforms".to_string(),
+        ))
+    }
+}
+
+/// Extension of entry files created by pass.
+const DEFAULT_EXTENSION: &str = "gpg";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pass::test_dir::TestDir;
+
+    #[test]
+    fn password_store_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<PasswordStore>();
+    }
+
+    #[test]
+    fn reload_reports_changes() {
+        let store_dir = TestDir::new("reload");
+        fs::write(store_dir.join("kept.gpg"), b"cipher").unwrap();
+        fs::write(store_dir.join("removed.gpg"), b"cipher").unwrap();
+        fs::write(store_dir.join("modified.gpg"), b"cipher").unwrap();
+        let store = PasswordStore::open(&*store_dir);
+
+        fs::remove_file(store_dir.join("removed.gpg")).unwrap();
+        fs::write(store_dir.join("modified.gpg"), b"longer cipher").unwrap();
+        fs::write(store_dir.join("added.gpg"), b"cipher").unwrap();
+        let changes = store.reload();
+        assert_eq!(changes.added, ["added"]);
+        assert_eq!(changes.removed, ["removed"]);
+        assert_eq!(changes.modified, ["modified"]);
+        assert!(store.reload().is_empty());
+
+        let before = store.snapshot();
+        fs::rename(store_dir.join("kept.gpg"), store_dir.join("moved.gpg")).unwrap();
+        store.reload();
+        let diff = store.diff(&before);
+        assert_eq!(diff.renamed, [("kept".to_string(), "moved".to_string())]);
+        assert!(diff.added.is_empty() && diff.removed.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_entries_keep_their_path() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let store_dir = TestDir::new("utf8");
+        let file_name = OsStr::from_bytes(b"caf\xe9.gpg");
+        fs::write(store_dir.join(file_name), b"cipher").unwrap();
+        let store = PasswordStore::open(&*store_dir);
+
+        let snapshot = store.snapshot();
+        assert_eq!(snapshot.len(), 1);
+        assert_eq!(snapshot[0].id, "caf\u{fffd}");
+        assert_eq!(
+            store.resolve_path(&snapshot[0].id),
+            store_dir.join(file_name)
+        );
+        assert!(store.get(&snapshot[0].id).is_some());
+    }
+
+    #[test]
+    fn builder_restricts_scan() {
+        let store_dir = TestDir::new("scope");
+        for id in ["top", "work/mail", "work/servers/db", "home/bank"] {
+            let path = store_dir.join(format!("{id}.gpg"));
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, b"cipher").unwrap();
+        }
+        let ids = |store: PasswordStore| -> Vec<String> {
+            store
+                .snapshot()
+                .iter()
+                .map(|info| info.id.clone())
+                .collect()
+        };
+
+        let store = PasswordStore::builder()
+            .store_dir(&*store_dir)
+            .prefix("work/");
+        assert_eq!(
+            ids(store.build().unwrap()),
+            ["work/mail", "work/servers/db"]
+        );
+        let store = PasswordStore::builder().store_dir(&*store_dir).max_depth(1);
+        assert_eq!(
+            ids(store.build().unwrap()),
+            ["home/bank", "top", "work/mail"]
+        );
+    }
+
+    #[test]
+    fn added_stores_are_prefixed() {
+        let root = TestDir::new("stores");
+        fs::create_dir_all(root.join("main/team")).unwrap();
+        fs::create_dir_all(root.join("team")).unwrap();
+        fs::write(root.join("main/own.gpg"), b"cipher").unwrap();
+        fs::write(root.join("main/team/hidden.gpg"), b"cipher").unwrap();
+        fs::write(root.join("team/server.gpg"), b"cipher").unwrap();
+        fs::write(root.join("team/.gpg-id"), b"team@example.org\n").unwrap();
+        let mut store = PasswordStore::open(root.join("main"));
+        store.add_store("team", root.join("team"));
+
+        let snapshot = store.snapshot();
+        let ids: Vec<&str> = snapshot.iter().map(|info| info.id.as_str()).collect();
+        assert_eq!(ids, ["own", "team/server"]);
+        assert_eq!(snapshot[1].store.as_deref(), Some("team"));
+        assert_eq!(
+            store.entry_path("team/server"),
+            root.join("team/server.gpg")
+        );
+        assert_eq!(
+            store.gpg_ids_for("team/server").unwrap(),
+            ["team@example.org"]
+        );
+    }
+
+    #[test]
+    fn rename_updates_aliases() {
+        let store_dir = TestDir::new("rename");
+        fs::create_dir_all(store_dir.join("old")).unwrap();
+        fs::write(store_dir.join("old/entry.gpg"), b"cipher").unwrap();
+        let store = PasswordStore {
+            store_dir: store_dir.to_path_buf(),
+            ..Default::default()
+        };
+        store.refresh_entries(&[store_dir.join("old/entry.gpg")]);
+
+        store.alias("old/entry", "alias").unwrap();
+        store.set_favorite("old/entry", true).unwrap();
+        store.rename("old/entry", "new/deeper/entry").unwrap();
+
+        assert!(!store_dir.join("old").exists());
+        assert_eq!(fs::read(store_dir.join("alias.gpg")).unwrap(), b"cipher");
+        let ids: Vec<String> = store
+            .snapshot()
+            .iter()
+            .map(|info| info.id.clone())
+            .collect();
+        assert_eq!(ids, ["alias", "new/deeper/entry"]);
+        assert!(store.display_metadata("old/entry").unwrap().is_none());
+        assert!(
+            store
+                .display_metadata("new/deeper/entry")
+                .unwrap()
+                .unwrap()
+                .favorite
+        );
+
+        // The metadata is moved back by the same undo
+        assert!(store.undo().unwrap().unwrap().starts_with("Rename"));
+        assert!(store_dir.join("old/entry.gpg").exists());
+        assert!(
+            store
+                .display_metadata("old/entry")
+                .unwrap()
+                .unwrap()
+                .favorite
+        );
+    }
+
+    #[test]
+    fn configured_extensions_are_scanned() {
+        let store_dir = TestDir::new("ext");
+        fs::write(store_dir.join("gpg.gpg"), b"cipher").unwrap();
+        fs::write(store_dir.join("age.age"), b"cipher").unwrap();
+        let mut store = PasswordStore::open(&*store_dir);
+        assert_eq!(store.snapshot().len(), 1);
+
+        store.set_extensions(vec!["gpg".to_string(), "age".to_string()]);
+        let entries: Vec<(String, String)> = store
+            .snapshot()
+            .iter()
+            .map(|info| (info.id.clone(), info.extension.clone()))
+            .collect();
+        assert_eq!(
+            entries,
+            [
+                ("age".to_string(), "age".to_string()),
+                ("gpg".to_string(), "gpg".to_string())
+            ]
+        );
+        assert_eq!(store.entry_path("age"), store_dir.join("age.age"));
+        assert!(store.checked_path("web/entry.AGE").is_err());
+        assert!(store.checked_path("web/entry.gpg").is_err());
+        assert!(store.checked_path("web/entry.txt").is_ok());
+    }
+
+    #[test]
+    fn registered_decryptor_is_used() {
+        struct Reversed;
+        impl Decryptor for Reversed {
+            fn decrypt(&self, cipher: &[u8]) -> Result<Vec<u8>, Error> {
+                Ok(cipher.iter().rev().copied().collect())
+            }
+        }
+
+        let store_dir = TestDir::new("kms");
+        fs::write(store_dir.join("entry.kms"), b"terces").unwrap();
+        let mut store = PasswordStore::open(&*store_dir);
+        store.register_decryptor("kms", Box::new(Reversed));
+
+        assert_eq!(store.snapshot()[0].id, "entry");
+        assert_eq!(store.fetch_binary("entry").unwrap(), b"secret");
+        assert!(store.set_field("entry", "user", "alice").is_err());
+        assert!(store.reencryption_report().unwrap().is_empty());
+    }
+
+    #[test]
+    fn gpg_ids_for_nearest_file() {
+        let store_dir = TestDir::new("gpg-id");
+        fs::create_dir_all(store_dir.join("team/deeper")).unwrap();
+        fs::write(store_dir.join(".gpg-id"), "alice@example.org\n").unwrap();
+        fs::write(
+            store_dir.join("team/.gpg-id"),
+            "# team keys\nbob@example.org carol@example.org\n\ndave@example.org\n",
+        )
+        .unwrap();
+        let store = PasswordStore::open(&*store_dir);
+
+        assert_eq!(store.gpg_ids_for("entry").unwrap(), ["alice@example.org"]);
+        assert_eq!(store.root_gpg_ids().unwrap(), ["alice@example.org"]);
+        assert!(store.gpg_ids_for("").is_err());
+        assert_eq!(
+            store.gpg_ids_for("team/deeper/entry").unwrap(),
+            ["bob@example.org", "carol@example.org", "dave@example.org"]
+        );
+    }
+
+    /// Stores the plaintext behind the recipients, to test without GnuPG.
+    struct PlainBackend;
+
+    impl CryptoBackend for PlainBackend {
+        fn decrypt(&self, cipher: &[u8]) -> Result<Vec<u8>, Error> {
+            let (_, plain) = cipher.split_at(cipher.iter().position(|b| *b == 0).unwrap() + 1);
+            Ok(plain.to_vec())
+        }
+
+        fn encrypt(&self, plain: &[u8], recipients: &[String]) -> Result<Vec<u8>, Error> {
+            let mut cipher = recipients.join(",").into_bytes();
+            cipher.push(0);
+            cipher.extend_from_slice(plain);
+            Ok(cipher)
         }
+    }
+
+    #[test]
+    fn custom_backend_is_used() {
+        let store_dir = TestDir::new("backend");
+        fs::write(store_dir.join(".gpg-id"), "alice@example.org\n").unwrap();
+        let mut store = PasswordStore::open(&*store_dir);
+        store.set_backend(Backend::Custom(Box::new(PlainBackend)));
 
-        visit_dir(dir, &mut result)?;
-        Ok(result)
+        store.insert_multiline("entry", "secret\n").unwrap();
+        assert_eq!(
+            fs::read(store_dir.join("entry.gpg")).unwrap(),
+            b"alice@example.org\0secret\n"
+        );
+        store.set_field("entry", "login", "alice").unwrap();
+        assert_eq!(
+            store.fetch_binary("entry").unwrap(),
+            b"secret\nlogin: alice\n"
+        );
     }
 }
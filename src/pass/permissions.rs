@@ -0,0 +1,58 @@
+use std::{env, fs, io, path::Path};
+
+#[cfg(unix)]
+use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt, PermissionsExt};
+
+use super::PasswordStore;
+use crate::PasswordEvent;
+
+/// Default umask of pass, files are only accessible by the owner.
+const DEFAULT_UMASK: u32 = 0o077;
+
+/// Returns the umask for files in the store, read from `PASSWORD_STORE_UMASK`.
+pub fn umask() -> u32 {
+    env::var("PASSWORD_STORE_UMASK")
+        .ok()
+        .and_then(|umask| u32::from_str_radix(umask.trim(), 8).ok())
+        .filter(|umask| *umask <= 0o777)
+        .unwrap_or(DEFAULT_UMASK)
+}
+
+/// Creates a directory and its parents, honoring the store umask.
+pub fn create_dir_all(path: &Path) -> io::Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    builder.mode(0o777 & !umask());
+    builder.create(path)
+}
+
+/// Applies the store umask to the options for creating a file.
+pub fn apply_file_mode(options: &mut fs::OpenOptions) -> &mut fs::OpenOptions {
+    #[cfg(unix)]
+    options.mode(0o666 & !umask());
+    options
+}
+
+impl PasswordStore {
+    /// Checks the permissions of all entries and returns the IDs of those that are
+    /// accessible by the group or others.
+    ///
+    /// Sends a [`PasswordEvent::InsecurePermissions`] for every such entry. Always
+    /// returns an empty list on platforms without Unix permissions.
+    pub fn check_permissions(&self) -> Vec<String> {
+        let mut insecure = Vec::new();
+        #[cfg(unix)]
+        for info in self.snapshot().iter() {
+            let mode = info.metadata.permissions().mode() & 0o777;
+            if mode & 0o077 != 0 {
+                self.send_event(PasswordEvent::InsecurePermissions {
+                    pass_id: info.id.clone(),
+                    mode,
+                });
+                insecure.push(info.id.clone());
+            }
+        }
+        insecure
+    }
+}
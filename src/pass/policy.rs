@@ -0,0 +1,121 @@
+use std::{
+    fs,
+    time::{Duration, SystemTime},
+};
+use toml::{Table, Value};
+
+use super::{GenerateOptions, PasswordStore};
+use crate::Error;
+
+/// Name of the policy file that applies to a directory and its subdirectories.
+const POLICY_FILE: &str = ".pass-policy";
+
+/// Password rules for the entries of a directory, read from a `.pass-policy` file.
+///
+/// The file is a TOML table with the optional keys `length`, `charset` and
+/// `expiry_days`. Like `.gpg-id`, the nearest file in the directory of an entry or
+/// one of its parents applies.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PasswordPolicy {
+    /// Number of characters of generated passwords
+    pub length: Option<usize>,
+    /// Characters to choose from for generated passwords
+    pub charset: Option<String>,
+    /// Number of days after which a password should be changed
+    pub expiry_days: Option<u64>,
+}
+
+impl PasswordPolicy {
+    fn from_table(table: &Table) -> Result<Self, Error> {
+        let positive = |key: &str| -> Result<Option<u64>, Error> {
+            match table.get(key) {
+                None => Ok(None),
+                Some(value) => value
+                    .as_integer()
+                    .filter(|value| *value > 0)
+                    .map(|value| Some(value as u64))
+                    .ok_or_else(|| Error::Pass(format!("invalid {key} in {POLICY_FILE}"))),
+            }
+        };
+        let charset = match table.get("charset") {
+            None => None,
+            Some(Value::String(charset)) if !charset.is_empty() => Some(charset.clone()),
+            Some(_) => return Err(Error::Pass(format!("invalid charset in {POLICY_FILE}"))),
+        };
+        Ok(Self {
+            length: positive("length")?.map(|length| length as usize),
+            charset,
+            expiry_days: positive("expiry_days")?,
+        })
+    }
+
+    /// Returns the options with the length and character set of the policy applied.
+    pub fn apply(&self, options: &GenerateOptions) -> GenerateOptions {
+        let mut options = options.clone();
+        if let Some(length) = self.length {
+            options.length = length;
+        }
+        if let Some(ref charset) = self.charset {
+            options.character_set = Some(charset.clone());
+        }
+        options
+    }
+
+    /// Returns the maximum age of a password.
+    pub fn expiry(&self) -> Option<Duration> {
+        self.expiry_days
+            .map(|days| Duration::from_secs(days * 24 * 60 * 60))
+    }
+
+    /// Checks whether a password last changed at `modified` has expired.
+    pub fn is_expired(&self, modified: SystemTime) -> bool {
+        self.expiry().is_some_and(|expiry| {
+            SystemTime::now()
+                .duration_since(modified)
+                .is_ok_and(|age| age >= expiry)
+        })
+    }
+}
+
+impl PasswordStore {
+    /// Returns the policy from the nearest `.pass-policy` file of an entry.
+    ///
+    /// Returns `None` if no policy applies, fails if the file is invalid.
+    pub fn policy_for(&self, pass_id: &str) -> Result<Option<PasswordPolicy>, Error> {
+        let path = self.checked_path(pass_id)?;
+        let store_dir = self.store_root(&path);
+        for dir in path.ancestors().skip(1) {
+            if !dir.starts_with(store_dir) {
+                break;
+            }
+            let policy = dir.join(POLICY_FILE);
+            if policy.is_file() {
+                let table: Table = fs::read_to_string(policy)?.parse()?;
+                return PasswordPolicy::from_table(&table).map(Some);
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_policy() {
+        let table: Table = "length = 32\ncharset = \"abc\"\nexpiry_days = 90\n"
+            .parse()
+            .unwrap();
+        let policy = PasswordPolicy::from_table(&table).unwrap();
+        assert_eq!(policy.length, Some(32));
+        assert_eq!(policy.expiry(), Some(Duration::from_secs(90 * 86400)));
+
+        let options = policy.apply(&GenerateOptions::default());
+        assert_eq!(options.length, 32);
+        assert_eq!(options.character_set.as_deref(), Some("abc"));
+
+        let table: Table = "length = 0\n".parse().unwrap();
+        assert!(PasswordPolicy::from_table(&table).is_err());
+    }
+}
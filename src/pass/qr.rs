@@ -0,0 +1,75 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use super::{fields, PasswordStore, SecretString};
+use crate::Error;
+
+/// A secret to be shown as a QR code, e.g. to transfer it to a phone.
+///
+/// Like `pass show --qrcode`, rendering uses `qrencode`, which receives the secret
+/// on stdin.
+#[derive(Debug)]
+pub struct QrCode {
+    data: SecretString,
+}
+
+impl QrCode {
+    /// Renders the code with Unicode block characters for a terminal.
+    pub fn to_terminal(&self) -> Result<String, Error> {
+        Ok(String::from_utf8(self.encode("UTF8")?)?)
+    }
+
+    /// Renders the code as a PNG image.
+    pub fn to_png(&self) -> Result<Vec<u8>, Error> {
+        self.encode("PNG")
+    }
+
+    /// Renders the code as an SVG image.
+    pub fn to_svg(&self) -> Result<String, Error> {
+        Ok(String::from_utf8(self.encode("SVG")?)?)
+    }
+
+    fn encode(&self, format: &str) -> Result<Vec<u8>, Error> {
+        let mut child = Command::new("qrencode")
+            .args(["--type", format, "--output", "-"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| Error::Pass(format!("QR codes require qrencode: {e}")))?;
+        child
+            .stdin
+            .take()
+            .expect("stdin should be piped")
+            .write_all(self.data.expose_secret().as_bytes())?;
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            return Err(Error::Pass("qrencode failed".to_string()));
+        }
+        Ok(output.stdout)
+    }
+}
+
+impl PasswordStore {
+    /// Returns a QR code of the password of an entry, or of the value of a field.
+    ///
+    /// The field `otpauth` selects the OTP URI, so that the seed can be imported
+    /// into an authenticator app.
+    pub fn render_qr(&self, pass_id: &str, field: Option<&str>) -> Result<QrCode, Error> {
+        self.touch();
+        let contents = SecretString::new(self.decrypt_file(&self.checked_path(pass_id)?)?);
+        self.record_use(pass_id);
+        let contents = contents.expose_secret();
+        let data = match field {
+            None => contents.lines().next(),
+            Some("otpauth") => contents.lines().find(|line| line.starts_with("otpauth://")),
+            Some(key) => fields::find_field(contents, key),
+        }
+        .ok_or_else(|| Error::Pass(format!("no {} found", field.unwrap_or("password"))))?;
+        Ok(QrCode {
+            data: SecretString::new(data.to_string()),
+        })
+    }
+}
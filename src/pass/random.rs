@@ -0,0 +1,24 @@
+use std::{fs::File, io::Read};
+
+use crate::Error;
+
+/// Fills the buffer with bytes from the operating system's random number generator.
+pub fn fill_bytes(buffer: &mut [u8]) -> Result<(), Error> {
+    File::open("/dev/urandom")?.read_exact(buffer)?;
+    Ok(())
+}
+
+/// Returns a uniformly distributed random number below `bound`.
+pub fn below(bound: u32) -> Result<u32, Error> {
+    assert!(bound > 0, "bound must be positive");
+    // Reject values from the incomplete last interval to avoid modulo bias
+    let zone = u32::MAX - (u32::MAX % bound);
+    loop {
+        let mut bytes = [0; 4];
+        fill_bytes(&mut bytes)?;
+        let value = u32::from_ne_bytes(bytes);
+        if value < zone {
+            return Ok(value % bound);
+        }
+    }
+}
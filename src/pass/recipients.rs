@@ -0,0 +1,44 @@
+use super::{CryptoBackend, PasswordStore};
+use crate::Error;
+
+/// Usability of a recipient key for encryption.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyStatus {
+    Usable,
+    /// No key for the GPG ID is in the keyring
+    Missing,
+    Expired,
+    Revoked,
+    /// The key is disabled or has no subkey that can encrypt
+    Unusable,
+}
+
+/// A GPG ID from a `.gpg-id` file resolved against the keyring.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecipientKey {
+    pub gpg_id: String,
+    /// Fingerprint of the primary key, `None` if the key is missing
+    pub fingerprint: Option<String>,
+    /// Primary user ID of the key
+    pub user_id: Option<String>,
+    /// Fingerprints of the primary key and subkeys that can encrypt
+    pub encryption_keys: Vec<String>,
+    pub status: KeyStatus,
+}
+
+impl RecipientKey {
+    pub fn is_usable(&self) -> bool {
+        self.status == KeyStatus::Usable
+    }
+}
+
+impl PasswordStore {
+    /// Resolves the GPG IDs an entry would be encrypted for against the keyring.
+    ///
+    /// Frontends can use the report to point out expired, revoked or missing keys
+    /// before an encryption attempt fails.
+    pub fn check_recipients(&self, pass_id: &str) -> Result<Vec<RecipientKey>, Error> {
+        let gpg_ids = self.gpg_ids_for(pass_id)?;
+        self.backend().inspect_keys(&gpg_ids)
+    }
+}
@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+
+use super::{operations, recipients_of, CryptoBackend, KeyId, PasswordStore, RecipientKey};
+use crate::{Error, PasswordEvent};
+
+/// An entry that is not encrypted for the recipients of its `.gpg-id` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecipientDrift {
+    pub pass_id: String,
+    /// GPG IDs the entry is not encrypted for, including IDs without a key in the
+    /// keyring
+    pub missing: Vec<String>,
+    /// Keys the entry is encrypted for that belong to none of the GPG IDs, including
+    /// anonymous recipients
+    pub unexpected: Vec<KeyId>,
+}
+
+impl PasswordStore {
+    /// Re-encrypts all entries below `path_prefix` for their current recipients.
+    ///
+    /// This is needed after a `.gpg-id` file changed, like `pass init` does for the
+    /// directory. An empty prefix re-encrypts the whole store. Aliases are skipped,
+    /// since the entries they point to are re-encrypted. Sends a
+    /// [`PasswordEvent::ReencryptionProgress`] for every entry and returns the number
+    /// of re-encrypted entries.
+    ///
+    /// This operation is synchronous and will block until all entries are processed.
+    pub fn reencrypt(&self, path_prefix: &str) -> Result<usize, Error> {
+        self.touch();
+        let prefix = path_prefix.trim_matches('/');
+        let entries: Vec<(String, std::path::PathBuf)> = self
+            .snapshot()
+            .iter()
+            .filter(|info| is_below(&info.id, prefix))
+            .map(|info| (info.id.clone(), self.entry_path(&info.id)))
+            .filter(|(_, path)| !path.is_symlink())
+            // Entries of custom decryptors cannot be encrypted again
+            .filter(|(_, path)| self.ensure_writable(path).is_ok())
+            .collect();
+        let paths: Vec<_> = entries.iter().map(|(_, path)| path.clone()).collect();
+        let total = entries.len();
+
+        let description = if prefix.is_empty() {
+            "Reencrypt password store using new GPG id.".to_string()
+        } else {
+            format!("Reencrypt {prefix} using new GPG id.")
+        };
+        self.write_operation(description, &paths, || {
+            for (done, (pass_id, path)) in entries.iter().enumerate() {
+                let recipients = self.gpg_ids_for(pass_id)?;
+                let contents = self.read_entry(path)?;
+                operations::write_entry(&self.backend(), path, &contents, &recipients)?;
+                self.send_event(PasswordEvent::ReencryptionProgress {
+                    pass_id: pass_id.clone(),
+                    done: done + 1,
+                    total,
+                });
+            }
+            Ok(total)
+        })
+    }
+
+    /// Finds entries that are not encrypted for the current recipients.
+    ///
+    /// The recipients of every entry are read from its session key packets without
+    /// decrypting it and compared to the keys of the GPG IDs from its `.gpg-id`
+    /// file, e.g. to find entries that a new team member can not decrypt yet.
+    /// Aliases and entries of custom decryptors are skipped.
+    /// [`reencrypt`](Self::reencrypt) fixes the reported entries.
+    pub fn reencryption_report(&self) -> Result<Vec<RecipientDrift>, Error> {
+        self.touch();
+        let mut keys: HashMap<Vec<String>, Vec<RecipientKey>> = HashMap::new();
+        let mut report = Vec::new();
+        for info in self.snapshot().iter() {
+            let path = self.entry_path(&info.id);
+            // Entries of custom decryptors have no OpenPGP recipients
+            if path.is_symlink() || self.ensure_writable(&path).is_err() {
+                continue;
+            }
+            let gpg_ids = self.gpg_ids_for(&info.id)?;
+            if !keys.contains_key(&gpg_ids) {
+                let inspected = self.backend().inspect_keys(&gpg_ids)?;
+                keys.insert(gpg_ids.clone(), inspected);
+            }
+            let expected = &keys[&gpg_ids];
+            let actual = recipients_of(&path)?;
+
+            let missing: Vec<String> = expected
+                .iter()
+                .filter(|key| {
+                    !key.encryption_keys.iter().any(|fingerprint| {
+                        actual
+                            .iter()
+                            .any(|key_id| key_id.matches_fingerprint(fingerprint))
+                    })
+                })
+                .map(|key| key.gpg_id.clone())
+                .collect();
+            let unexpected: Vec<KeyId> = actual
+                .into_iter()
+                .filter(|key_id| {
+                    !expected
+                        .iter()
+                        .flat_map(|key| &key.encryption_keys)
+                        .any(|fingerprint| key_id.matches_fingerprint(fingerprint))
+                })
+                .collect();
+            if !missing.is_empty() || !unexpected.is_empty() {
+                report.push(RecipientDrift {
+                    pass_id: info.id.clone(),
+                    missing,
+                    unexpected,
+                });
+            }
+        }
+        Ok(report)
+    }
+}
+
+/// Checks whether the ID is equal to or inside of the directory `prefix`.
+pub(super) fn is_below(pass_id: &str, prefix: &str) -> bool {
+    prefix.is_empty()
+        || pass_id
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
@@ -0,0 +1,225 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::Sender,
+        Mutex,
+    },
+};
+
+use super::{ignore::IgnoreRules, walk, PasswordInfo};
+use crate::PasswordEvent;
+
+/// How directories that are symbolic links are scanned for entries.
+///
+/// Entries that are symbolic links, like aliases, are always listed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SymlinkPolicy {
+    /// Scan linked directories, their entries are listed below every link, except
+    /// for links to a parent
+    #[default]
+    Follow,
+    /// Ignore linked directories
+    Skip,
+    /// Ignore linked directories and send a [`PasswordEvent::SymlinkSkipped`] for
+    /// each of them
+    Report,
+}
+
+/// Settings for finding the entry files of a store, which can be moved to another
+/// thread for streaming.
+#[derive(Debug, Clone)]
+pub(super) struct Scanner {
+    pub(super) store_dir: PathBuf,
+    /// Other stores by the ID prefix their entries are shown under
+    pub(super) mounts: Vec<(String, PathBuf)>,
+    pub(super) extensions: Vec<String>,
+    pub(super) symlinks: SymlinkPolicy,
+    /// Patterns of paths to hide in addition to the ignore file
+    pub(super) excludes: Vec<String>,
+    /// Folder the scan is restricted to, like `work`, empty for the whole store
+    pub(super) prefix: String,
+    /// Maximal number of folders an entry may be nested in below its store
+    pub(super) max_depth: Option<usize>,
+    pub(super) event_tx: Option<Sender<PasswordEvent>>,
+}
+
+impl Scanner {
+    /// Passes the entries of the store and the added stores to `emit` in no
+    /// particular order, until it returns `false`.
+    ///
+    /// Only the entries below the prefix and within the maximal depth are scanned.
+    pub(super) fn scan<E>(&self, emit: E)
+    where
+        E: Fn(PasswordInfo) -> bool + Sync,
+    {
+        let stopped = AtomicBool::new(false);
+        let emit = |info| {
+            let more = emit(info);
+            if !more {
+                stopped.store(true, Ordering::Relaxed);
+            }
+            more
+        };
+        let is_mounted = |id: &str| {
+            self.mounts
+                .iter()
+                .any(|(name, _)| strip_folder(id, name).is_some_and(|rest| !rest.is_empty()))
+        };
+
+        // A prefix inside of an added store only needs that store to be scanned
+        if !self
+            .mounts
+            .iter()
+            .any(|(name, _)| strip_folder(&self.prefix, name).is_some())
+        {
+            self.scan_dir(&self.store_dir, &self.prefix, self.max_depth, |info| {
+                is_mounted(&info.id) || emit(info)
+            });
+        }
+        for (name, dir) in &self.mounts {
+            let prefix = if self.prefix.is_empty() {
+                ""
+            } else {
+                match strip_folder(&self.prefix, name) {
+                    Some(prefix) => prefix,
+                    None => continue,
+                }
+            };
+            if stopped.load(Ordering::Relaxed) {
+                return;
+            }
+            self.scan_dir(dir, prefix, self.max_depth, |mut info| {
+                info.id = format!("{name}/{}", info.id);
+                info.store = Some(name.clone());
+                emit(info)
+            });
+        }
+    }
+
+    /// Returns the entries of the store and the added stores, unsorted.
+    pub(super) fn collect(&self) -> Vec<PasswordInfo> {
+        let passwords = Mutex::new(Vec::new());
+        self.scan(|info| {
+            passwords
+                .lock()
+                .expect("another thread holding the lock paniced")
+                .push(info);
+            true
+        });
+        passwords
+            .into_inner()
+            .expect("another thread holding the lock paniced")
+    }
+
+    /// Returns the entries below `dir` with IDs relative to it, unsorted.
+    pub(super) fn collect_dir(&self, dir: &Path) -> Vec<PasswordInfo> {
+        let passwords = Mutex::new(Vec::new());
+        self.scan_dir(dir, "", None, |info| {
+            passwords
+                .lock()
+                .expect("another thread holding the lock paniced")
+                .push(info);
+            true
+        });
+        passwords
+            .into_inner()
+            .expect("another thread holding the lock paniced")
+    }
+
+    /// Passes the entries below the folder `prefix` of `dir` to `emit`.
+    ///
+    /// Folders that cannot be read are skipped, a [`PasswordEvent::DirectorySkipped`]
+    /// is sent for each of them.
+    fn scan_dir<E>(&self, dir: &Path, prefix: &str, max_depth: Option<usize>, emit: E)
+    where
+        E: Fn(PasswordInfo) -> bool + Sync,
+    {
+        let within_depth = |path: &Path| {
+            max_depth.is_none_or(|max_depth| {
+                path.strip_prefix(dir)
+                    .is_ok_and(|relative| relative.components().count() <= max_depth)
+            })
+        };
+        let ignore = IgnoreRules::load(dir, &self.excludes);
+        let is_ignored = |path: &Path, is_dir| {
+            !ignore.is_empty()
+                && path
+                    .strip_prefix(dir)
+                    .is_ok_and(|relative| ignore.is_ignored(&relative.to_string_lossy(), is_dir))
+        };
+        let skipped = walk::walk(
+            &dir.join(prefix),
+            // Skip hidden directories like .git and .trash
+            |path, is_symlink| {
+                !is_hidden(path)
+                    && within_depth(path)
+                    && !is_ignored(path, true)
+                    && (!is_symlink || self.descend_link(path))
+            },
+            |path| {
+                path.extension().is_some_and(|ext| {
+                    self.extensions
+                        .iter()
+                        .any(|accepted| ext.eq_ignore_ascii_case(accepted))
+                }) && !is_ignored(path, false)
+            },
+            |path, metadata| {
+                let relative_path = path
+                    .strip_prefix(dir)
+                    .expect("store_dir should be a prefix");
+                // Get pass ID
+                let pass_id = relative_path.with_extension("").to_string_lossy().into();
+                let extension = path
+                    .extension()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned();
+                emit(
+                    PasswordInfo::new(pass_id, metadata)
+                        .with_extension(extension)
+                        .with_path(relative_path),
+                )
+            },
+        );
+        if let Some(ref event_tx) = self.event_tx {
+            for (path, error) in skipped {
+                let _ = event_tx.send(PasswordEvent::DirectorySkipped {
+                    path,
+                    error: error.to_string(),
+                });
+            }
+        }
+    }
+
+    fn descend_link(&self, path: &Path) -> bool {
+        match self.symlinks {
+            SymlinkPolicy::Follow => true,
+            SymlinkPolicy::Skip => false,
+            SymlinkPolicy::Report => {
+                if let Some(ref event_tx) = self.event_tx {
+                    let _ = event_tx.send(PasswordEvent::SymlinkSkipped {
+                        path: path.to_path_buf(),
+                    });
+                }
+                false
+            }
+        }
+    }
+}
+
+/// Returns the rest of the ID inside of the folder, which is empty for the folder
+/// itself.
+pub(super) fn strip_folder<'a>(pass_id: &'a str, folder: &str) -> Option<&'a str> {
+    let rest = pass_id.strip_prefix(folder)?;
+    if rest.is_empty() {
+        Some(rest)
+    } else {
+        rest.strip_prefix('/')
+    }
+}
+
+pub(super) fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+}
@@ -0,0 +1,317 @@
+use std::{ops::Range, sync::mpsc::Sender};
+
+use super::{ignore, PasswordInfo, PasswordStore, StoreSnapshot};
+use crate::PasswordEvent;
+
+const SCORE_MATCH: i64 = 16;
+const BONUS_CONSECUTIVE: i64 = 8;
+const BONUS_WORD_START: i64 = 12;
+const BONUS_PATH_START: i64 = 4;
+const BONUS_CAMEL_CASE: i64 = 10;
+const PENALTY_GAP: i64 = 1;
+
+/// A search result with the ranges of the ID that matched the query.
+///
+/// The ranges are byte offsets into `id`, sorted and non-overlapping, so that
+/// frontends can highlight the matched characters with `&id[range]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScoredMatch {
+    pub id: String,
+    pub score: i64,
+    pub ranges: Vec<Range<usize>>,
+}
+
+impl PasswordStore {
+    /// Searches the password IDs with a fuzzy matcher.
+    ///
+    /// Results are sorted by descending score, ties are resolved by ID.
+    pub fn search(&self, query: &str) -> Vec<ScoredMatch> {
+        self.touch();
+        search_ids(self.snapshot().iter().map(|info| info.id.as_str()), query)
+    }
+
+    /// Returns the entries with IDs matching a shell-style glob like `web/*/admin`,
+    /// like `pass find`.
+    ///
+    /// See [`StoreSnapshot::find`].
+    pub fn find(&self, glob: &str) -> Vec<PasswordInfo> {
+        self.touch();
+        self.snapshot().find(glob).into_iter().cloned().collect()
+    }
+
+    /// Starts an incremental search session over the current password IDs.
+    pub fn search_session(&self) -> SearchSession {
+        SearchSession::new(self.snapshot().iter().map(|info| info.id.clone()).collect())
+    }
+}
+
+impl StoreSnapshot {
+    /// Returns the entries with IDs matching a shell-style glob like `web/*/admin`.
+    ///
+    /// `*` and `?` do not match a `/` and `**` matches any number of folders. A
+    /// glob without a `/` is matched against the entry names, so `*mail*` finds
+    /// entries in every folder.
+    pub fn find(&self, glob: &str) -> Vec<&PasswordInfo> {
+        let glob = glob.trim_matches('/');
+        self.iter()
+            .filter(|info| {
+                if glob.contains('/') {
+                    ignore::glob_matches(glob, &info.id)
+                } else {
+                    let name = info.id.rsplit('/').next().unwrap_or_default();
+                    ignore::glob_matches(glob, name)
+                }
+            })
+            .collect()
+    }
+}
+
+/// An incremental search that is updated keystroke by keystroke.
+///
+/// When a query extends the previous one, only the previous results are matched
+/// again, since every ID matching the longer query also matched the shorter one.
+#[derive(Debug)]
+pub struct SearchSession {
+    ids: Vec<String>,
+    query: String,
+    results: Vec<ScoredMatch>,
+    generation: u64,
+    event_tx: Option<Sender<PasswordEvent>>,
+}
+
+impl SearchSession {
+    /// Creates a session over the given IDs, matching all of them initially.
+    pub fn new(ids: Vec<String>) -> Self {
+        let results = search_ids(ids.iter().map(String::as_str), "");
+        Self {
+            ids,
+            query: String::new(),
+            results,
+            generation: 0,
+            event_tx: None,
+        }
+    }
+
+    /// Sends a [`PasswordEvent::SearchResults`] after every update.
+    pub fn with_event_sender(mut self, event_tx: Sender<PasswordEvent>) -> Self {
+        self.event_tx = Some(event_tx);
+        self
+    }
+
+    /// Updates the query and returns the ranked results.
+    pub fn update(&mut self, query: &str) -> &[ScoredMatch] {
+        if query == self.query {
+            return &self.results;
+        }
+
+        self.results = if !self.query.is_empty() && query.starts_with(&self.query) {
+            search_ids(self.results.iter().map(|m| m.id.as_str()), query)
+        } else {
+            search_ids(self.ids.iter().map(String::as_str), query)
+        };
+        self.query = query.to_string();
+        self.generation += 1;
+
+        if let Some(ref event_tx) = self.event_tx {
+            let _ = event_tx.send(PasswordEvent::SearchResults {
+                generation: self.generation,
+                query: self.query.clone(),
+                matches: self.results.clone(),
+            });
+        }
+        &self.results
+    }
+
+    /// Returns the current query.
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// Returns the results of the current query.
+    pub fn results(&self) -> &[ScoredMatch] {
+        &self.results
+    }
+
+    /// Returns the number of updates so far.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+}
+
+/// Runs the fuzzy matcher over a list of IDs and returns the ranked matches.
+pub(crate) fn search_ids<'a>(ids: impl Iterator<Item = &'a str>, query: &str) -> Vec<ScoredMatch> {
+    let mut matches: Vec<ScoredMatch> = ids
+        .filter_map(|id| {
+            fuzzy_match(query, id).map(|(score, ranges)| ScoredMatch {
+                id: id.to_string(),
+                score,
+                ranges,
+            })
+        })
+        .collect();
+    matches.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.id.cmp(&b.id)));
+    matches
+}
+
+/// Matches the characters of `query` in order against `text`.
+///
+/// The case is ignored unless the query contains uppercase characters. Like fzf and
+/// skim, the alignment with the best score over all possible positions is chosen,
+/// which rewards consecutive characters and characters at the start of words or
+/// path components. Returns the score and the matched byte ranges in `text`, or
+/// `None` if not all characters of the query could be found.
+pub fn fuzzy_match(query: &str, text: &str) -> Option<(i64, Vec<Range<usize>>)> {
+    let query: Vec<char> = query.chars().filter(|c| !c.is_whitespace()).collect();
+    if query.is_empty() {
+        return Some((0, Vec::new()));
+    }
+    let case_sensitive = query.iter().any(|c| c.is_uppercase());
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let (n, m) = (chars.len(), query.len());
+    if m > n {
+        return None;
+    }
+    let matches = |i: usize, j: usize| {
+        let c = chars[j].1;
+        if case_sensitive {
+            c == query[i]
+        } else {
+            chars_eq(c, query[i])
+        }
+    };
+    let bonuses: Vec<i64> = (0..n).map(|j| bonus(&chars, j)).collect();
+
+    // scores[i][j] is the best score of the first i + 1 query characters with the
+    // last one at position j, from[i][j] the position of the previous character
+    let mut scores = vec![vec![None; n]; m];
+    let mut from = vec![vec![0; n]; m];
+    for j in 0..n {
+        if matches(0, j) {
+            scores[0][j] = Some(SCORE_MATCH + bonuses[j] - PENALTY_GAP * j as i64);
+        }
+    }
+    for i in 1..m {
+        // Best score of the previous row before j - 1, adjusted so that the gap
+        // penalty only depends on the current position
+        let mut best_gapped: Option<(i64, usize)> = None;
+        for j in i..n {
+            if j >= 2 {
+                if let Some(score) = scores[i - 1][j - 2] {
+                    let adjusted = score + PENALTY_GAP * (j - 2) as i64;
+                    if best_gapped.is_none_or(|(best, _)| adjusted > best) {
+                        best_gapped = Some((adjusted, j - 2));
+                    }
+                }
+            }
+            if !matches(i, j) {
+                continue;
+            }
+            let consecutive = scores[i - 1][j - 1].map(|score| (score + BONUS_CONSECUTIVE, j - 1));
+            let gapped =
+                best_gapped.map(|(adjusted, k)| (adjusted - PENALTY_GAP * (j - 1) as i64, k));
+            let best = match (consecutive, gapped) {
+                (Some(a), Some(b)) => Some(if b.0 > a.0 { b } else { a }),
+                (a, b) => a.or(b),
+            };
+            if let Some((score, k)) = best {
+                scores[i][j] = Some(score + SCORE_MATCH + bonuses[j]);
+                from[i][j] = k;
+            }
+        }
+    }
+
+    // Earlier matches win ties, so that the ranges are deterministic
+    let (score, mut position) = (0..n)
+        .filter_map(|j| scores[m - 1][j].map(|score| (score, j)))
+        .fold(None, |best: Option<(i64, usize)>, (score, j)| match best {
+            Some((best_score, _)) if best_score >= score => best,
+            _ => Some((score, j)),
+        })?;
+    let mut positions = vec![0; m];
+    for i in (0..m).rev() {
+        positions[i] = position;
+        position = from[i][position];
+    }
+
+    Some((score, to_ranges(&chars, &positions, text.len())))
+}
+
+/// Returns the bonus for matching the character at the position.
+fn bonus(chars: &[(usize, char)], position: usize) -> i64 {
+    let Some(&(_, previous)) = position.checked_sub(1).and_then(|i| chars.get(i)) else {
+        return BONUS_WORD_START + BONUS_PATH_START;
+    };
+    let current = chars[position].1;
+    if previous == '/' {
+        BONUS_WORD_START + BONUS_PATH_START
+    } else if !previous.is_alphanumeric() && current.is_alphanumeric() {
+        BONUS_WORD_START
+    } else if previous.is_lowercase() && current.is_uppercase() {
+        BONUS_CAMEL_CASE
+    } else {
+        0
+    }
+}
+
+fn chars_eq(a: char, b: char) -> bool {
+    a == b || a.to_lowercase().eq(b.to_lowercase())
+}
+
+fn to_ranges(chars: &[(usize, char)], positions: &[usize], text_len: usize) -> Vec<Range<usize>> {
+    let byte_end = |position: usize| chars.get(position + 1).map_or(text_len, |(i, _)| *i);
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    for &position in positions {
+        let start = chars[position].0;
+        let end = byte_end(position);
+        match ranges.last_mut() {
+            Some(last) if last.end == start => last.end = end,
+            _ => ranges.push(start..end),
+        }
+    }
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_match_ranges() {
+        let (_, ranges) = fuzzy_match("gh", "web/github").unwrap();
+        assert_eq!(ranges, vec![4..5, 7..8]);
+
+        let (_, ranges) = fuzzy_match("git", "web/github").unwrap();
+        assert_eq!(ranges, vec![4..7]);
+
+        assert!(fuzzy_match("xyz", "web/github").is_none());
+    }
+
+    #[test]
+    fn fuzzy_match_prefers_word_starts() {
+        let (word_start, _) = fuzzy_match("mail", "email/mail").unwrap();
+        let (inner, _) = fuzzy_match("mail", "email").unwrap();
+        assert!(word_start > inner);
+
+        // The best alignment is found even if it does not start at the first match
+        let (_, ranges) = fuzzy_match("sgit", "s/gi/github").unwrap();
+        assert_eq!(ranges, vec![0..1, 5..8]);
+    }
+
+    #[test]
+    fn fuzzy_match_smart_case() {
+        assert!(fuzzy_match("gh", "web/GitHub").is_some());
+        assert!(fuzzy_match("GH", "web/GitHub").is_some());
+        assert!(fuzzy_match("GH", "web/github").is_none());
+    }
+
+    #[test]
+    fn search_session_narrows() {
+        let ids = vec!["mail".to_string(), "web/github".to_string()];
+        let mut session = SearchSession::new(ids);
+        assert_eq!(session.results().len(), 2);
+        assert_eq!(session.update("g").len(), 1);
+        assert_eq!(session.update("gh")[0].id, "web/github");
+        assert_eq!(session.update("").len(), 2);
+        assert_eq!(session.generation(), 3);
+    }
+}
@@ -0,0 +1,126 @@
+use std::{cmp::Ordering, iter::Peekable, str::Chars};
+
+use super::{PasswordInfo, PasswordStore, StoreSnapshot};
+
+/// Order of the entries returned by [`PasswordStore::sorted_entries`].
+///
+/// Snapshots are always sorted by ID, ties of the other orders are resolved by ID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortOrder {
+    /// Lexicographic order of the IDs, like `pass ls`
+    #[default]
+    Id,
+    /// Numbers in IDs are compared by their value, so that `key2` comes before
+    /// `key10`, and letters ignore their case
+    Natural,
+    /// Lexicographic order of the IDs, ignoring case
+    CaseInsensitive,
+    /// Most recently modified entries first
+    Modified,
+    /// Most frequently and recently used entries first, see
+    /// [`PasswordInfo::frecency`]
+    Frecency,
+}
+
+impl SortOrder {
+    /// Compares two entries in this order.
+    pub fn compare(&self, a: &PasswordInfo, b: &PasswordInfo) -> Ordering {
+        let ordering = match self {
+            SortOrder::Id => Ordering::Equal,
+            SortOrder::Natural => natural_cmp(&a.id, &b.id),
+            SortOrder::CaseInsensitive => a.id.to_lowercase().cmp(&b.id.to_lowercase()),
+            SortOrder::Modified => {
+                let modified = |info: &PasswordInfo| info.metadata.modified().ok();
+                modified(b).cmp(&modified(a))
+            }
+            SortOrder::Frecency => b.frecency.total_cmp(&a.frecency),
+        };
+        ordering.then_with(|| a.id.cmp(&b.id))
+    }
+}
+
+impl StoreSnapshot {
+    /// Returns the entries of the snapshot in the order.
+    pub fn sorted(&self, order: SortOrder) -> Vec<&PasswordInfo> {
+        let mut entries: Vec<&PasswordInfo> = self.iter().collect();
+        if order != SortOrder::Id {
+            entries.sort_by(|a, b| order.compare(a, b));
+        }
+        entries
+    }
+}
+
+impl PasswordStore {
+    /// Sets the order of [`sorted_entries`](Self::sorted_entries).
+    pub fn sort_by(&mut self, order: SortOrder) {
+        self.sort_order = order;
+    }
+
+    pub fn sort_order(&self) -> SortOrder {
+        self.sort_order
+    }
+
+    /// Returns the entries in the order set with [`sort_by`](Self::sort_by).
+    ///
+    /// The frecency of the entries includes the accesses since they were read.
+    pub fn sorted_entries(&self) -> Vec<PasswordInfo> {
+        let mut entries: Vec<PasswordInfo> = self.snapshot().iter().cloned().collect();
+        if self.sort_order == SortOrder::Frecency {
+            self.apply_frecency(&mut entries);
+        }
+        if self.sort_order != SortOrder::Id {
+            entries.sort_by(|a, b| self.sort_order.compare(a, b));
+        }
+        entries
+    }
+}
+
+/// Compares runs of digits by their value and other characters ignoring case.
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a.chars().peekable(), b.chars().peekable());
+    loop {
+        match (a.peek().copied(), b.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let (x, y) = (take_number(&mut a), take_number(&mut b));
+                // Without leading zeros, longer numbers are larger
+                let ordering = x.len().cmp(&y.len()).then_with(|| x.cmp(&y));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(x), Some(y)) => {
+                let ordering = x.to_lowercase().cmp(y.to_lowercase());
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+                a.next();
+                b.next();
+            }
+        }
+    }
+}
+
+fn take_number(chars: &mut Peekable<Chars>) -> String {
+    let mut number = String::new();
+    while let Some(c) = chars.next_if(char::is_ascii_digit) {
+        if !(number.is_empty() && c == '0') {
+            number.push(c);
+        }
+    }
+    number
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn natural_order() {
+        let mut ids = vec!["key10", "Key2", "key02b", "key1", "bank"];
+        ids.sort_by(|a, b| natural_cmp(a, b).then_with(|| a.cmp(b)));
+        assert_eq!(ids, vec!["bank", "key1", "Key2", "key02b", "key10"]);
+    }
+}
@@ -0,0 +1,61 @@
+use std::{
+    env,
+    fs::{File, TryLockError},
+    path::{Path, PathBuf},
+};
+
+use super::{cache::store_hash, permissions};
+use crate::Error;
+
+/// Exclusive lock on a password store, released when dropped.
+///
+/// All mutating operations of [`PasswordStore`](crate::PasswordStore) hold this
+/// lock, so that several applications using this library can not modify the store
+/// concurrently. `pass` itself does not know about the lock, applications invoking
+/// it can hold the lock themselves in the meantime.
+///
+/// The lock file is kept outside of the store, so that it does not show up in its
+/// git repository, in `$XDG_RUNTIME_DIR/passepartout/` or, if that is not set, the
+/// cache directory of the user.
+#[derive(Debug)]
+pub struct StoreLock {
+    _file: File,
+}
+
+impl StoreLock {
+    /// Acquires the lock, blocking until it is released by other holders.
+    pub fn acquire(store_dir: &Path) -> Result<Self, Error> {
+        let file = Self::open(store_dir)?;
+        file.lock()?;
+        Ok(Self { _file: file })
+    }
+
+    /// Acquires the lock if it is not held by someone else.
+    pub fn try_acquire(store_dir: &Path) -> Result<Option<Self>, Error> {
+        let file = Self::open(store_dir)?;
+        match file.try_lock() {
+            Ok(()) => Ok(Some(Self { _file: file })),
+            Err(TryLockError::WouldBlock) => Ok(None),
+            Err(TryLockError::Error(e)) => Err(e.into()),
+        }
+    }
+
+    fn open(store_dir: &Path) -> Result<File, Error> {
+        let path = lock_path(store_dir);
+        permissions::create_dir_all(path.parent().expect("lock path should have a parent"))?;
+        Ok(
+            permissions::apply_file_mode(File::options().read(true).write(true).create(true))
+                .truncate(false)
+                .open(path)?,
+        )
+    }
+}
+
+/// Returns the lock file of the store, named after the hash of its path.
+fn lock_path(store_dir: &Path) -> PathBuf {
+    dirs::runtime_dir()
+        .or_else(dirs::cache_dir)
+        .unwrap_or_else(env::temp_dir)
+        .join("passepartout")
+        .join(format!("{}.lock", store_hash(store_dir)))
+}
@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+
+use super::{EntryBuilder, PasswordStore};
+use crate::Error;
+
+/// Key of the value for the first line of an entry.
+const PASSWORD_KEY: &str = "password";
+/// Key of the value that is written as an `otpauth://` URI line.
+const OTPAUTH_KEY: &str = "otpauth";
+/// Key of the value for free-form notes at the end of an entry.
+const NOTES_KEY: &str = "notes";
+
+#[derive(Debug, Clone)]
+struct TemplateField {
+    key: String,
+    required: bool,
+}
+
+/// A template for new entries, see [`PasswordStore::insert_from_template`].
+///
+/// The password is always required. Fields are written as `key: value` lines in
+/// the order they were added, except for the keys `otpauth` and `notes`, which are
+/// placed like [`EntryBuilder::otpauth`] and [`EntryBuilder::notes`] do.
+#[derive(Debug, Clone)]
+pub struct EntryTemplate {
+    name: String,
+    fields: Vec<TemplateField>,
+}
+
+impl EntryTemplate {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            fields: Vec::new(),
+        }
+    }
+
+    /// Adds a field that must have a non-empty value.
+    pub fn required(mut self, key: impl Into<String>) -> Self {
+        self.fields.push(TemplateField {
+            key: key.into(),
+            required: true,
+        });
+        self
+    }
+
+    /// Adds a field that is left out of the entry if no value is given.
+    pub fn optional(mut self, key: impl Into<String>) -> Self {
+        self.fields.push(TemplateField {
+            key: key.into(),
+            required: false,
+        });
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the keys of the fields in order, without the password.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.fields.iter().map(|field| field.key.as_str())
+    }
+
+    /// Validates the values and returns the contents of the entry.
+    ///
+    /// Fails if the password or a required field is missing or empty, or if a value
+    /// is given for a key that is not part of the template.
+    pub fn render(&self, values: &HashMap<String, String>) -> Result<String, Error> {
+        if let Some(key) = values
+            .keys()
+            .find(|key| *key != PASSWORD_KEY && !self.keys().any(|k| k == key.as_str()))
+        {
+            return Err(Error::Pass(format!(
+                "unknown field {key} for template {}",
+                self.name
+            )));
+        }
+        let value = |key: &str| values.get(key).filter(|value| !value.is_empty());
+
+        let password = value(PASSWORD_KEY)
+            .ok_or_else(|| Error::Pass(format!("missing password for template {}", self.name)))?;
+        let mut builder = EntryBuilder::new(password);
+        for field in &self.fields {
+            let Some(value) = value(&field.key) else {
+                if field.required {
+                    return Err(Error::Pass(format!(
+                        "missing required field {} for template {}",
+                        field.key, self.name
+                    )));
+                }
+                continue;
+            };
+            builder = match field.key.as_str() {
+                OTPAUTH_KEY => builder.otpauth(value),
+                NOTES_KEY => builder.notes(value),
+                key => builder.field(key, value),
+            };
+        }
+        Ok(builder.build())
+    }
+}
+
+impl PasswordStore {
+    /// Creates a new encrypted entry from a registered template.
+    ///
+    /// The values are validated with [`EntryTemplate::render`]. Fails if an entry
+    /// with the ID already exists.
+    ///
+    /// This operation is synchronous and will block until encryption completes.
+    pub fn insert_from_template(
+        &self,
+        pass_id: &str,
+        template: &str,
+        values: &HashMap<String, String>,
+    ) -> Result<(), Error> {
+        let contents = self
+            .template(template)
+            .ok_or_else(|| Error::Pass(format!("no template found for {template}")))?
+            .render(values)?;
+        self.insert_multiline(pass_id, &contents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_validates_fields() {
+        let template = EntryTemplate::new("website login")
+            .required("user")
+            .optional("url")
+            .optional("otpauth");
+        let mut values = HashMap::from([
+            ("password".to_string(), "secret".to_string()),
+            (
+                "otpauth".to_string(),
+                "otpauth://totp/x?secret=AB".to_string(),
+            ),
+        ]);
+        assert!(template.render(&values).is_err());
+
+        values.insert("user".to_string(), "alice".to_string());
+        assert_eq!(
+            template.render(&values).unwrap(),
+            "secret\nuser: alice\notpauth://totp/x?secret=AB\n"
+        );
+
+        values.insert("pin".to_string(), "1234".to_string());
+        assert!(template.render(&values).is_err());
+    }
+}
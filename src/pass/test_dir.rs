@@ -0,0 +1,50 @@
+use std::{
+    env, fs,
+    ops::Deref,
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Number of directories created by this process, to keep their names unique.
+static CREATED: AtomicUsize = AtomicUsize::new(0);
+
+/// An empty directory below the temporary directory for a test, removed together
+/// with its contents when dropped.
+#[derive(Debug)]
+pub(super) struct TestDir(PathBuf);
+
+impl TestDir {
+    /// Creates the directory, the name is unique across tests running in parallel.
+    pub(super) fn new(name: &str) -> Self {
+        let path = env::temp_dir().join(format!(
+            "passepartout-{name}-{}-{}",
+            process::id(),
+            CREATED.fetch_add(1, Ordering::SeqCst)
+        ));
+        // Left over by an earlier process with the same ID that did not clean up
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).expect("failed to create test directory");
+        Self(path)
+    }
+}
+
+impl Deref for TestDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TestDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,200 @@
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::Error;
+
+/// How often a waiting operation checks for cancellation.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Handle for cancelling pending operations from another thread.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationHandle(Arc<AtomicBool>);
+
+impl CancellationHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels all operations using this handle, they return [`Error::Cancelled`].
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// A decryption queued for the worker thread.
+type Job = Box<dyn FnOnce() + Send>;
+
+/// The worker thread and the number it was started as.
+struct Worker {
+    id: u64,
+    sender: mpsc::Sender<Job>,
+}
+
+/// Sends jobs to the worker thread, which is started on first use.
+static WORKER: Mutex<Option<Worker>> = Mutex::new(None);
+
+/// Number of the next worker thread.
+static NEXT_WORKER_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Runs `f` and returns its result unless the timeout expires or the handle is
+/// cancelled before it completes.
+///
+/// Without a timeout `f` runs on the current thread and the handle is only checked
+/// before it starts. Otherwise it runs on a long-lived worker thread, which keeps
+/// its GPG context between decryptions. When a decryption is abandoned the worker
+/// is left to finish it and later ones get a new worker, so a stuck gpg doesn't
+/// block them.
+pub(super) fn run_with_limits<T: Send + 'static>(
+    timeout: Option<Duration>,
+    cancellation: &CancellationHandle,
+    f: impl FnOnce() -> Result<T, Error> + Send + 'static,
+) -> Result<T, Error> {
+    if cancellation.is_cancelled() {
+        return Err(Error::Cancelled);
+    }
+    let Some(timeout) = timeout else {
+        return f();
+    };
+    let (tx, rx) = mpsc::channel();
+    let worker_id = send_to_worker(Box::new(move || {
+        let _ = tx.send(f());
+    }))?;
+
+    let deadline = Instant::now() + timeout;
+    let error = loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break Error::Timeout;
+        }
+        match rx.recv_timeout(remaining.min(POLL_INTERVAL)) {
+            Ok(result) => return result,
+            Err(mpsc::RecvTimeoutError::Timeout) if cancellation.is_cancelled() => {
+                break Error::Cancelled
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                return Err(Error::Pass("decryption thread panicked".to_string()))
+            }
+        }
+    };
+    abandon_worker(worker_id);
+    Err(error)
+}
+
+/// Queues the job on the worker thread, starting it if necessary, and returns the
+/// ID of the worker.
+fn send_to_worker(job: Job) -> Result<u64, Error> {
+    let mut worker = WORKER
+        .lock()
+        .expect("another thread holding the lock paniced");
+    let worker = match &mut *worker {
+        Some(worker) => worker,
+        None => {
+            let (sender, jobs) = mpsc::channel::<Job>();
+            thread::Builder::new()
+                .name("passepartout-decrypt".to_string())
+                .spawn(move || {
+                    for job in jobs {
+                        // A panicking job drops its sender, which is reported to the caller
+                        let _ = panic::catch_unwind(AssertUnwindSafe(job));
+                    }
+                })?;
+            let id = NEXT_WORKER_ID.fetch_add(1, Ordering::SeqCst);
+            worker.insert(Worker { id, sender })
+        }
+    };
+    worker
+        .sender
+        .send(job)
+        .map_err(|_| Error::Pass("decryption thread stopped".to_string()))?;
+    Ok(worker.id)
+}
+
+/// Stops sending jobs to the worker, it exits after the jobs already queued.
+fn abandon_worker(id: u64) {
+    let mut worker = WORKER
+        .lock()
+        .expect("another thread holding the lock paniced");
+    if worker.as_ref().is_some_and(|worker| worker.id == id) {
+        *worker = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Serializes the tests, which replace the shared worker.
+    static WORKER_TESTS: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn timeout_and_cancellation() {
+        let _serial = WORKER_TESTS.lock().unwrap();
+        let slow = || {
+            thread::sleep(Duration::from_millis(200));
+            Ok(())
+        };
+        let handle = CancellationHandle::new();
+        let result = run_with_limits(Some(Duration::from_millis(10)), &handle, slow);
+        assert!(matches!(result, Err(Error::Timeout)));
+
+        let canceller = handle.clone();
+        thread::spawn(move || canceller.cancel());
+        assert!(matches!(
+            run_with_limits(Some(Duration::from_secs(10)), &handle, slow),
+            Err(Error::Cancelled)
+        ));
+        assert!(matches!(
+            run_with_limits(None, &handle, || Ok(1)),
+            Err(Error::Cancelled)
+        ));
+        assert_eq!(
+            run_with_limits(None, &CancellationHandle::new(), || Ok(1)).unwrap(),
+            1
+        );
+    }
+
+    #[test]
+    fn stuck_job_does_not_block_later_ones() {
+        let _serial = WORKER_TESTS.lock().unwrap();
+        let handle = CancellationHandle::new();
+        let stuck = || {
+            thread::sleep(Duration::from_secs(5));
+            Ok(0)
+        };
+        let result = run_with_limits(Some(Duration::from_millis(10)), &handle, stuck);
+        assert!(matches!(result, Err(Error::Timeout)));
+
+        let started = Instant::now();
+        let result = run_with_limits(Some(Duration::from_secs(2)), &handle, || Ok(1));
+        assert_eq!(result.unwrap(), 1);
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn reuses_worker_thread() {
+        let _serial = WORKER_TESTS.lock().unwrap();
+        let current = || Ok(thread::current().id());
+        let handle = CancellationHandle::new();
+        assert_eq!(
+            run_with_limits(None, &handle, current).unwrap(),
+            thread::current().id()
+        );
+
+        let timeout = Some(Duration::from_secs(10));
+        let worker = run_with_limits(timeout, &handle, current).unwrap();
+        assert_ne!(worker, thread::current().id());
+        assert_eq!(run_with_limits(timeout, &handle, current).unwrap(), worker);
+    }
+}
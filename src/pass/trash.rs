@@ -0,0 +1,223 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use super::{permissions, PasswordStore};
+use crate::Error;
+
+/// Directory inside the store that holds removed entries.
+const TRASH_DIR: &str = ".trash";
+
+/// Number of batches created by this process, to keep their names unique.
+static BATCH_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// An entry in the trash, see [`PasswordStore::list_trash`].
+#[derive(Debug, Clone)]
+pub struct TrashEntry {
+    pub id: String,
+    pub deleted_at: SystemTime,
+    pub path: PathBuf,
+}
+
+impl PasswordStore {
+    /// Moves an entry into the trash instead of deleting it permanently.
+    ///
+    /// Removed entries are kept in `.trash/<timestamp>/` below the directory of the
+    /// store containing them, which is hidden from the entry list.
+    pub fn trash(&self, pass_id: &str) -> Result<(), Error> {
+        self.touch();
+        let path = self.checked_path(pass_id)?;
+        if path.symlink_metadata().is_err() {
+            return Err(Error::Pass(format!("no entry found for {pass_id}")));
+        }
+        if let Some(retention) = self.trash_retention() {
+            self.empty_trash(retention)?;
+        }
+
+        // Within the store of the entry, since renaming fails across file systems
+        let store_dir = self.store_root(&path);
+        let relative_path = path.strip_prefix(store_dir).unwrap_or(&path);
+        let trash_path = store_dir
+            .join(TRASH_DIR)
+            .join(batch_name(SystemTime::now()))
+            .join(relative_path);
+        if trash_path.symlink_metadata().is_ok() {
+            return Err(Error::Pass(format!(
+                "{} is already in the trash",
+                trash_path.display()
+            )));
+        }
+        self.move_file(format!("Move {pass_id} to trash."), &path, &trash_path)
+    }
+
+    /// Lists the entries in the trash of the store and the added stores, most
+    /// recently deleted first.
+    pub fn list_trash(&self) -> Result<Vec<TrashEntry>, Error> {
+        let stores = std::iter::once((None, self.store_dir.as_path())).chain(
+            self.stores()
+                .into_iter()
+                .map(|(name, dir)| (Some(name), dir)),
+        );
+        let mut entries = Vec::new();
+        for (name, store_dir) in stores {
+            let trash_dir = store_dir.join(TRASH_DIR);
+            if !trash_dir.is_dir() {
+                continue;
+            }
+            for batch in fs::read_dir(&trash_dir)? {
+                let batch = batch?.path();
+                let Some(deleted_at) = batch
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .and_then(parse_batch_name)
+                else {
+                    continue;
+                };
+                for info in self.scan_store_dir(&batch) {
+                    entries.push(TrashEntry {
+                        path: batch.join(info.relative_path()),
+                        id: match name {
+                            Some(name) => format!("{name}/{}", info.id),
+                            None => info.id,
+                        },
+                        deleted_at,
+                    });
+                }
+            }
+        }
+        entries.sort_by(|a, b| {
+            b.deleted_at
+                .cmp(&a.deleted_at)
+                .then_with(|| a.id.cmp(&b.id))
+        });
+        Ok(entries)
+    }
+
+    /// Restores the most recently deleted entry with the given ID from the trash.
+    pub fn restore_from_trash(&self, pass_id: &str) -> Result<(), Error> {
+        self.touch();
+        let entry = self
+            .list_trash()?
+            .into_iter()
+            .find(|entry| entry.id == pass_id)
+            .ok_or_else(|| Error::Pass(format!("no entry found in trash for {pass_id}")))?;
+        let path = self.checked_path(pass_id)?;
+        if path.symlink_metadata().is_ok() {
+            return Err(Error::Pass(format!(
+                "an entry already exists for {pass_id}"
+            )));
+        }
+        self.move_file(format!("Restore {pass_id} from trash."), &entry.path, &path)
+    }
+
+    /// Permanently deletes entries that have been in the trash for longer than
+    /// `older_than` and returns their number.
+    pub fn empty_trash(&self, older_than: Duration) -> Result<usize, Error> {
+        self.touch();
+        let now = SystemTime::now();
+        let expired: Vec<PathBuf> = self
+            .list_trash()?
+            .into_iter()
+            .filter(|entry| {
+                now.duration_since(entry.deleted_at)
+                    .is_ok_and(|age| age >= older_than)
+            })
+            .map(|entry| entry.path)
+            .collect();
+        if expired.is_empty() {
+            return Ok(0);
+        }
+
+        self.write_operation("Empty trash.".to_string(), &expired, || {
+            for path in &expired {
+                fs::remove_file(path)?;
+                if let Some(parent) = path.parent() {
+                    self.prune_empty_dirs(parent);
+                }
+            }
+            Ok(expired.len())
+        })
+    }
+
+    fn move_file(&self, description: String, from: &Path, to: &Path) -> Result<(), Error> {
+        self.write_operation(description, &[from.to_path_buf(), to.to_path_buf()], || {
+            permissions::create_dir_all(to.parent().expect("entry path should have a parent"))?;
+            fs::rename(from, to)?;
+            if let Some(parent) = from.parent() {
+                self.prune_empty_dirs(parent);
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Returns a name for a new batch in the trash, like `1700000000-000000042-0`.
+///
+/// The name starts with the time of deletion and is unique, even for entries
+/// trashed within the same second.
+fn batch_name(deleted_at: SystemTime) -> String {
+    let timestamp = deleted_at.duration_since(UNIX_EPOCH).unwrap_or_default();
+    format!(
+        "{}-{:09}-{}",
+        timestamp.as_secs(),
+        timestamp.subsec_nanos(),
+        BATCH_COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// Returns the time of deletion of a batch, batches created by earlier versions
+/// are only named by the seconds.
+fn parse_batch_name(name: &str) -> Option<SystemTime> {
+    let mut parts = name.split('-');
+    let secs = parts.next()?.parse().ok()?;
+    let nanos = match parts.next() {
+        Some(nanos) => nanos.parse().ok()?,
+        None => 0,
+    };
+    Some(UNIX_EPOCH + Duration::new(secs, nanos))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pass::test_dir::TestDir;
+
+    #[test]
+    fn trash_keeps_entries_of_the_same_second() {
+        let root = TestDir::new("trash-batches");
+        fs::create_dir_all(root.join("main")).unwrap();
+        fs::create_dir_all(root.join("team")).unwrap();
+        let mut store = PasswordStore::open(root.join("main"));
+        store.add_store("team", root.join("team"));
+
+        for contents in [b"first", b"other"] {
+            fs::write(root.join("main/entry.gpg"), contents).unwrap();
+            store.trash("entry").unwrap();
+        }
+        fs::write(root.join("team/server.gpg"), b"cipher").unwrap();
+        store.trash("team/server").unwrap();
+
+        let trash = store.list_trash().unwrap();
+        let ids: Vec<&str> = trash.iter().map(|entry| entry.id.as_str()).collect();
+        assert_eq!(ids, ["team/server", "entry", "entry"]);
+        assert!(trash[0].path.starts_with(root.join("team/.trash")));
+        assert_eq!(fs::read(&trash[1].path).unwrap(), b"other");
+
+        store.restore_from_trash("team/server").unwrap();
+        assert!(root.join("team/server.gpg").is_file());
+    }
+
+    #[test]
+    fn batch_names_are_parsed() {
+        let deleted_at = UNIX_EPOCH + Duration::new(1_700_000_000, 42);
+        assert_eq!(parse_batch_name(&batch_name(deleted_at)), Some(deleted_at));
+        assert_eq!(
+            parse_batch_name("1700000000"),
+            Some(UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+        );
+        assert_eq!(parse_batch_name("notes"), None);
+    }
+}
@@ -0,0 +1,253 @@
+use super::{PasswordInfo, PasswordStore, StoreSnapshot};
+
+/// A folder of the store with its entries and subfolders, built from the IDs of
+/// the entries, see [`PasswordStore::tree`].
+///
+/// Folders and entries are sorted by name.
+#[derive(Debug, Clone, Default)]
+pub struct StoreTree {
+    /// Last component of the folder path, empty for the root
+    pub name: String,
+    /// Folder path relative to the store, like `work/servers`, empty for the root
+    pub path: String,
+    pub folders: Vec<StoreTree>,
+    pub entries: Vec<PasswordInfo>,
+}
+
+/// A folder or an entry visited by [`StoreTree::iter`], with its depth below the
+/// folder the traversal started from.
+#[derive(Debug, Clone, Copy)]
+pub enum TreeItem<'a> {
+    Folder(&'a StoreTree, usize),
+    Entry(&'a PasswordInfo, usize),
+}
+
+/// A child of a folder listed by [`PasswordStore::list_dir`].
+#[derive(Debug, Clone)]
+pub enum DirItem {
+    Folder {
+        name: String,
+        /// Folder path relative to the store, like `work/servers`
+        path: String,
+        /// Number of entries in the folder and all subfolders
+        entries: usize,
+    },
+    Entry(Box<PasswordInfo>),
+}
+
+impl StoreTree {
+    /// Groups the entries into folders by splitting their IDs on `/`.
+    pub fn from_entries(entries: &[PasswordInfo]) -> Self {
+        let mut root = Self::default();
+        for info in entries {
+            let mut folder = &mut root;
+            let mut components: Vec<&str> = info.id.split('/').collect();
+            components.pop();
+            for name in components {
+                let i = match folder
+                    .folders
+                    .binary_search_by(|f| f.name.as_str().cmp(name))
+                {
+                    Ok(i) => i,
+                    Err(i) => {
+                        let path = if folder.path.is_empty() {
+                            name.to_string()
+                        } else {
+                            format!("{}/{name}", folder.path)
+                        };
+                        folder.folders.insert(
+                            i,
+                            Self {
+                                name: name.to_string(),
+                                path,
+                                ..Default::default()
+                            },
+                        );
+                        i
+                    }
+                };
+                folder = &mut folder.folders[i];
+            }
+            folder.entries.push(info.clone());
+        }
+        root.sort_entries();
+        root
+    }
+
+    fn sort_entries(&mut self) {
+        self.entries.sort_by(|a, b| a.id.cmp(&b.id));
+        self.folders.iter_mut().for_each(Self::sort_entries);
+    }
+
+    /// Returns the folder with the path relative to this folder.
+    pub fn folder(&self, path: &str) -> Option<&StoreTree> {
+        path.split('/')
+            .filter(|name| !name.is_empty())
+            .try_fold(self, |folder, name| {
+                folder.folders.iter().find(|sub| sub.name == name)
+            })
+    }
+
+    /// Returns the entry with the ID, which is relative to the store like the paths.
+    pub fn entry(&self, pass_id: &str) -> Option<&PasswordInfo> {
+        let folder = match pass_id.rsplit_once('/') {
+            Some((path, _)) => self.folder(path.strip_prefix(&self.path)?)?,
+            None => self,
+        };
+        folder.entries.iter().find(|info| info.id == pass_id)
+    }
+
+    /// Returns the number of entries in the folder and all subfolders.
+    pub fn len(&self) -> usize {
+        self.entries.len() + self.folders.iter().map(Self::len).sum::<usize>()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Visits the subfolders and entries depth-first, each folder before its
+    /// contents and subfolders before entries, like a file manager lists them.
+    pub fn iter(&self) -> impl Iterator<Item = TreeItem<'_>> {
+        let mut items = Vec::new();
+        self.collect_items(0, &mut items);
+        items.into_iter()
+    }
+
+    fn collect_items<'a>(&'a self, depth: usize, items: &mut Vec<TreeItem<'a>>) {
+        for folder in &self.folders {
+            items.push(TreeItem::Folder(folder, depth));
+            folder.collect_items(depth + 1, items);
+        }
+        items.extend(self.entries.iter().map(|info| TreeItem::Entry(info, depth)));
+    }
+}
+
+impl StoreSnapshot {
+    /// Groups the entries of the snapshot into folders.
+    pub fn tree(&self) -> StoreTree {
+        StoreTree::from_entries(self)
+    }
+
+    /// Returns the subfolders and entries directly inside the folder `prefix`, the
+    /// root for an empty prefix.
+    ///
+    /// Subfolders come first and are sorted by name like the entries. Only the
+    /// entries below the prefix are visited.
+    pub fn list_dir(&self, prefix: &str) -> Vec<DirItem> {
+        list_dir(self, prefix)
+    }
+}
+
+impl PasswordStore {
+    /// Returns the entries grouped into folders.
+    pub fn tree(&self) -> StoreTree {
+        self.snapshot().tree()
+    }
+
+    /// Returns the subfolders and entries directly inside a folder, see
+    /// [`StoreSnapshot::list_dir`].
+    pub fn list_dir(&self, prefix: &str) -> Vec<DirItem> {
+        self.snapshot().list_dir(prefix)
+    }
+}
+
+/// Lists a folder of entries sorted by ID.
+fn list_dir(sorted: &[PasswordInfo], prefix: &str) -> Vec<DirItem> {
+    let prefix = prefix.trim_matches('/');
+    let prefix = if prefix.is_empty() {
+        String::new()
+    } else {
+        format!("{prefix}/")
+    };
+    // The entries below the prefix are adjacent, as the IDs are sorted
+    let start = sorted.partition_point(|info| info.id < prefix);
+    let below = sorted[start..]
+        .iter()
+        .take_while(|info| info.id.starts_with(&prefix));
+
+    let mut folders: Vec<DirItem> = Vec::new();
+    let mut entries = Vec::new();
+    for info in below {
+        let relative = &info.id[prefix.len()..];
+        let Some((name, _)) = relative.split_once('/') else {
+            entries.push(DirItem::Entry(Box::new(info.clone())));
+            continue;
+        };
+        match folders.last_mut() {
+            Some(DirItem::Folder {
+                name: last,
+                entries,
+                ..
+            }) if last == name => *entries += 1,
+            _ => folders.push(DirItem::Folder {
+                name: name.to_string(),
+                path: format!("{prefix}{name}"),
+                entries: 1,
+            }),
+        }
+    }
+    folders.sort_by(|a, b| match (a, b) {
+        (DirItem::Folder { name: a, .. }, DirItem::Folder { name: b, .. }) => a.cmp(b),
+        _ => std::cmp::Ordering::Equal,
+    });
+    folders.extend(entries);
+    folders
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn tree_groups_by_folder() {
+        let metadata = fs::metadata(env!("CARGO_MANIFEST_DIR")).unwrap();
+        let entries: Vec<PasswordInfo> =
+            ["bank", "work/mail", "work/servers/db", "work/servers/web"]
+                .into_iter()
+                .map(|id| PasswordInfo::new(id.to_string(), metadata.clone()))
+                .collect();
+        let tree = StoreTree::from_entries(&entries);
+
+        assert_eq!(tree.len(), 4);
+        let servers = tree.folder("work/servers").unwrap();
+        assert_eq!(servers.path, "work/servers");
+        assert_eq!(servers.entries.len(), 2);
+        assert!(tree.entry("work/servers/web").is_some());
+        assert!(tree.folder("work").unwrap().entry("work/mail").is_some());
+        let depths: Vec<usize> = tree
+            .iter()
+            .map(|item| match item {
+                TreeItem::Folder(_, depth) | TreeItem::Entry(_, depth) => depth,
+            })
+            .collect();
+        assert_eq!(depths, [0, 1, 2, 2, 1, 0]);
+    }
+
+    #[test]
+    fn list_dir_counts_entries() {
+        let metadata = fs::metadata(env!("CARGO_MANIFEST_DIR")).unwrap();
+        let entries: Vec<PasswordInfo> = ["work/a-b/x", "work/a/x", "work/a/y/z", "work/mail"]
+            .into_iter()
+            .map(|id| PasswordInfo::new(id.to_string(), metadata.clone()))
+            .collect();
+
+        let items: Vec<(String, usize)> = list_dir(&entries, "work/")
+            .into_iter()
+            .map(|item| match item {
+                DirItem::Folder { path, entries, .. } => (path, entries),
+                DirItem::Entry(info) => (info.id, 0),
+            })
+            .collect();
+        assert_eq!(
+            items,
+            [
+                ("work/a".to_string(), 2),
+                ("work/a-b".to_string(), 1),
+                ("work/mail".to_string(), 0)
+            ]
+        );
+        assert_eq!(list_dir(&entries, "").len(), 1);
+    }
+}
@@ -0,0 +1,436 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Mutex, MutexGuard, TryLockError},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use super::{atomic, cache::store_hash, PasswordInfo, PasswordStore};
+use crate::Error;
+
+/// Time after which the weight of an access is halved.
+const HALF_LIFE: Duration = Duration::from_secs(14 * 24 * 60 * 60);
+
+/// Number of entries whose usage is kept, the least recently used are dropped.
+const MAX_RECORDS: usize = 1000;
+
+/// Minimal time between two writes of the access records.
+const SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A recently used entry, see [`PasswordStore::recent`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecentAccess {
+    pub id: String,
+    pub accessed: SystemTime,
+}
+
+/// How often and when an entry was last used.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct AccessRecord {
+    count: u32,
+    last_access: SystemTime,
+}
+
+impl AccessRecord {
+    /// Returns the number of accesses, weighted by the time since the last one.
+    fn frecency(&self, now: SystemTime) -> f64 {
+        let age = now.duration_since(self.last_access).unwrap_or_default();
+        f64::from(self.count) * 0.5f64.powf(age.as_secs_f64() / HALF_LIFE.as_secs_f64())
+    }
+}
+
+/// Access records of the entries of a store, kept in the user data directory
+/// rather than the store, since they differ between devices.
+///
+/// Changes are written at most once per [`SAVE_INTERVAL`] and when the store is
+/// dropped, so that decrypting does not wait for the disk.
+#[derive(Debug, Default)]
+pub(super) struct Usage {
+    records: Mutex<Records>,
+    /// Held while writing the file, with the number of the last written change
+    written: Mutex<u64>,
+}
+
+#[derive(Debug, Default)]
+struct Records {
+    /// `None` until loaded
+    entries: Option<HashMap<String, AccessRecord>>,
+    /// File the records were loaded from
+    path: Option<PathBuf>,
+    /// Number of changes since the records were loaded
+    changes: u64,
+    /// Time of the last save, `None` if nothing was saved yet
+    saved_at: Option<Instant>,
+}
+
+impl Records {
+    fn get(&mut self, store_dir: &Path) -> &mut HashMap<String, AccessRecord> {
+        if self.entries.is_none() {
+            self.path = usage_path(store_dir);
+            self.entries = Some(self.path.as_deref().map(load).unwrap_or_default());
+        }
+        self.entries.as_mut().expect("records should be loaded")
+    }
+
+    /// Returns the file and its contents if the records changed since the last
+    /// save and it is due, or if `force` is set.
+    fn take_save(&mut self, written: u64, force: bool) -> Option<(PathBuf, String, u64)> {
+        let due = self
+            .saved_at
+            .is_none_or(|saved_at| saved_at.elapsed() >= SAVE_INTERVAL);
+        if self.changes == written || !(force || due) {
+            return None;
+        }
+        let path = self.path.clone()?;
+        let records = self.entries.as_mut()?;
+        prune(records);
+        self.saved_at = Some(Instant::now());
+        Some((path, serialize(records), self.changes))
+    }
+}
+
+impl Usage {
+    /// Writes the records if they changed, only if the last save is long enough ago
+    /// unless `force` is set.
+    fn save(&self, force: bool) -> Result<(), Error> {
+        let mut written = match self.written.try_lock() {
+            Ok(written) => written,
+            // The changes are included in the next save
+            Err(TryLockError::WouldBlock) if !force => return Ok(()),
+            Err(_) => self
+                .written
+                .lock()
+                .expect("another thread holding the lock paniced"),
+        };
+        let Some((path, contents, changes)) = self.records().take_save(*written, force) else {
+            return Ok(());
+        };
+        atomic::write(&path, contents.as_bytes())?;
+        *written = changes;
+        Ok(())
+    }
+
+    fn records(&self) -> MutexGuard<'_, Records> {
+        self.records
+            .lock()
+            .expect("another thread holding the lock paniced")
+    }
+}
+
+impl Drop for Usage {
+    fn drop(&mut self) {
+        let _ = self.save(true);
+    }
+}
+
+impl PasswordStore {
+    /// Records that an entry was used, which raises its
+    /// [`frecency`](Self::frecency).
+    ///
+    /// Decrypting entries through the store records them, frontends should call
+    /// this when they use the free functions like
+    /// [`copy_password`](crate::copy_password). Only the ID, the number of accesses
+    /// and the time of the last one are stored in the user data directory, see
+    /// [`save_usage`](Self::save_usage).
+    pub fn record_access(&self, pass_id: &str) -> Result<(), Error> {
+        let now = SystemTime::now();
+        let mut records = self.usage.records();
+        let record = records
+            .get(&self.store_dir)
+            .entry(pass_id.to_string())
+            .or_insert(AccessRecord {
+                count: 0,
+                last_access: now,
+            });
+        record.count = record.count.saturating_add(1);
+        record.last_access = now;
+        records.changes += 1;
+        drop(records);
+
+        self.usage.save(false)
+    }
+
+    /// Writes the access records that are not saved yet.
+    ///
+    /// Records are saved at most once a minute and when the store is dropped, a
+    /// frontend can call this before exiting without dropping the store.
+    pub fn save_usage(&self) -> Result<(), Error> {
+        self.usage.save(true)
+    }
+
+    /// Returns the current frecency of an entry, `0.0` if it was never used.
+    ///
+    /// Unlike [`PasswordInfo::frecency`], which is set when the entries are read,
+    /// this includes the accesses since then.
+    pub fn frecency(&self, pass_id: &str) -> f64 {
+        self.usage
+            .records()
+            .get(&self.store_dir)
+            .get(pass_id)
+            .map_or(0.0, |record| record.frecency(SystemTime::now()))
+    }
+
+    /// Returns up to `limit` entries ordered from most to least recently used.
+    ///
+    /// Entries that were removed since are skipped. See
+    /// [`read_recent`](Self::read_recent) for showing them before the store is
+    /// opened.
+    pub fn recent(&self, limit: usize) -> Vec<RecentAccess> {
+        let snapshot = self.snapshot();
+        let mut records = self.usage.records();
+        let recent = records
+            .get(&self.store_dir)
+            .iter()
+            .filter(|(id, _)| snapshot.get(id).is_some());
+        most_recent(recent, limit)
+    }
+
+    /// Returns up to `limit` entries of the store directory ordered from most to
+    /// least recently used, without reading the entries.
+    ///
+    /// Launchers can show them immediately at startup, the entries may not exist
+    /// anymore.
+    pub fn read_recent(store_dir: impl AsRef<Path>, limit: usize) -> Vec<RecentAccess> {
+        let records = usage_path(store_dir.as_ref())
+            .map(|path| load(&path))
+            .unwrap_or_default();
+        most_recent(records.iter(), limit)
+    }
+
+    /// Records that an entry was used by an operation of the store.
+    pub(super) fn record_use(&self, pass_id: &str) {
+        // The usage is not essential, failing to store it must not fail the operation
+        let _ = self.record_access(pass_id);
+    }
+
+    /// Sets the frecency of the entries from the access records.
+    pub(super) fn apply_frecency(&self, passwords: &mut [PasswordInfo]) {
+        let now = SystemTime::now();
+        let mut records = self.usage.records();
+        let records = records.get(&self.store_dir);
+        if records.is_empty() {
+            return;
+        }
+        for info in passwords {
+            info.frecency = records
+                .get(&info.id)
+                .map_or(0.0, |record| record.frecency(now));
+        }
+    }
+
+    /// Moves the access records of a renamed entry.
+    pub(super) fn rename_usage(&self, old_id: &str, new_id: &str) -> Result<(), Error> {
+        let mut records = self.usage.records();
+        let entries = records.get(&self.store_dir);
+        let Some(record) = entries.remove(old_id) else {
+            return Ok(());
+        };
+        entries.insert(new_id.to_string(), record);
+        records.changes += 1;
+        drop(records);
+
+        self.usage.save(false)
+    }
+}
+
+fn most_recent<'a>(
+    records: impl Iterator<Item = (&'a String, &'a AccessRecord)>,
+    limit: usize,
+) -> Vec<RecentAccess> {
+    let mut recent: Vec<RecentAccess> = records
+        .map(|(id, record)| RecentAccess {
+            id: id.clone(),
+            accessed: record.last_access,
+        })
+        .collect();
+    recent.sort_by(|a, b| b.accessed.cmp(&a.accessed).then_with(|| a.id.cmp(&b.id)));
+    recent.truncate(limit);
+    recent
+}
+
+/// Keeps the [`MAX_RECORDS`] most recently used entries, ties are resolved by ID.
+fn prune(records: &mut HashMap<String, AccessRecord>) {
+    if records.len() <= MAX_RECORDS {
+        return;
+    }
+    let mut by_access: Vec<(&String, &AccessRecord)> = records.iter().collect();
+    by_access.sort_by(|(a_id, a), (b_id, b)| {
+        b.last_access
+            .cmp(&a.last_access)
+            .then_with(|| a_id.cmp(b_id))
+    });
+    let dropped: Vec<String> = by_access[MAX_RECORDS..]
+        .iter()
+        .map(|(id, _)| (*id).clone())
+        .collect();
+    for id in dropped {
+        records.remove(&id);
+    }
+}
+
+fn serialize(records: &HashMap<String, AccessRecord>) -> String {
+    let mut contents = String::new();
+    for (id, record) in records {
+        let last_access = record
+            .last_access
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        contents.push_str(&format!(
+            "{}\t{}\t{id}\n",
+            record.count,
+            last_access.as_secs()
+        ));
+    }
+    contents
+}
+
+fn load(path: &Path) -> HashMap<String, AccessRecord> {
+    fs::read_to_string(path)
+        .map(|contents| contents.lines().filter_map(parse_line).collect())
+        .unwrap_or_default()
+}
+
+fn usage_path(store_dir: &Path) -> Option<PathBuf> {
+    Some(
+        dirs::data_local_dir()?
+            .join("passepartout")
+            .join(format!("{}.usage", store_hash(store_dir))),
+    )
+}
+
+fn parse_line(line: &str) -> Option<(String, AccessRecord)> {
+    let mut parts = line.splitn(3, '\t');
+    let count = parts.next()?.parse().ok()?;
+    let last_access = UNIX_EPOCH + Duration::from_secs(parts.next()?.parse().ok()?);
+    let id = parts.next()?.to_string();
+    Some((id, AccessRecord { count, last_access }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pass::test_dir::TestDir;
+
+    #[test]
+    fn frecency_decays() {
+        let (id, record) = parse_line("4\t1700000000\tweb/mail").unwrap();
+        assert_eq!(id, "web/mail");
+        assert_eq!(record.frecency(record.last_access), 4.0);
+        assert_eq!(record.frecency(record.last_access + HALF_LIFE), 2.0);
+        assert_eq!(parse_line("garbage"), None);
+    }
+
+    fn record(count: u32, secs: u64) -> AccessRecord {
+        AccessRecord {
+            count,
+            last_access: UNIX_EPOCH + Duration::from_secs(secs),
+        }
+    }
+
+    #[test]
+    fn recent_skips_removed_entries() {
+        let store_dir = TestDir::new("recent");
+        for id in ["mail", "bank", "chat"] {
+            fs::write(store_dir.join(format!("{id}.gpg")), b"cipher").unwrap();
+        }
+        let store = PasswordStore::open(&*store_dir);
+        *store.usage.records() = Records {
+            entries: Some(HashMap::from([
+                ("mail".to_string(), record(9, 100)),
+                ("bank".to_string(), record(1, 300)),
+                ("removed".to_string(), record(1, 400)),
+                ("chat".to_string(), record(2, 200)),
+            ])),
+            ..Default::default()
+        };
+
+        let ids = |limit| -> Vec<String> {
+            store
+                .recent(limit)
+                .into_iter()
+                .map(|recent| recent.id)
+                .collect()
+        };
+        assert_eq!(ids(10), ["bank", "chat", "mail"]);
+        assert_eq!(ids(2), ["bank", "chat"]);
+        assert!(ids(0).is_empty());
+        assert_eq!(
+            store.recent(1)[0].accessed,
+            UNIX_EPOCH + Duration::from_secs(300)
+        );
+    }
+
+    #[test]
+    fn read_recent_includes_removed_entries() {
+        let dir = TestDir::new("read-recent");
+        let path = dir.join("store.usage");
+        fs::write(
+            &path,
+            "1\t100\tmail\ngarbage\n3\t300\tremoved\n2\t100\tbank\n",
+        )
+        .unwrap();
+
+        let recent = most_recent(load(&path).iter(), 10);
+        let ids: Vec<&str> = recent.iter().map(|recent| recent.id.as_str()).collect();
+        assert_eq!(ids, ["removed", "bank", "mail"]);
+        assert_eq!(most_recent(load(&path).iter(), 1).len(), 1);
+        assert!(load(&dir.join("missing.usage")).is_empty());
+    }
+
+    #[test]
+    fn saves_are_debounced() {
+        let dir = TestDir::new("usage");
+        let path = dir.join("store.usage");
+        let record = AccessRecord {
+            count: 1,
+            last_access: UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+        };
+        let usage = Usage {
+            records: Mutex::new(Records {
+                entries: Some(HashMap::from([("web/mail".to_string(), record)])),
+                path: Some(path.clone()),
+                changes: 1,
+                saved_at: None,
+            }),
+            written: Mutex::new(0),
+        };
+
+        usage.save(false).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "1\t1700000000\tweb/mail\n"
+        );
+        fs::remove_file(&path).unwrap();
+        usage.records().changes += 1;
+        usage.save(false).unwrap();
+        assert!(!path.exists());
+        drop(usage);
+        assert!(path.exists());
+    }
+
+    #[test]
+    fn prune_keeps_exactly_max_records() {
+        let last_access = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let mut records: HashMap<String, AccessRecord> = (0..MAX_RECORDS + 10)
+            .map(|i| {
+                let record = AccessRecord {
+                    count: 1,
+                    last_access,
+                };
+                (format!("entry{i:04}"), record)
+            })
+            .collect();
+        let newest = AccessRecord {
+            count: 1,
+            last_access: last_access + Duration::from_secs(1),
+        };
+        records.insert("newest".to_string(), newest);
+
+        prune(&mut records);
+        assert_eq!(records.len(), MAX_RECORDS);
+        assert!(records.contains_key("newest"));
+        assert!(records.contains_key("entry0000"));
+        assert!(!records.contains_key(&format!("entry{:04}", MAX_RECORDS - 1)));
+    }
+}
@@ -0,0 +1,326 @@
+use std::{
+    fs::{self, Metadata},
+    io,
+    path::{Path, PathBuf},
+    sync::{Arc, Condvar, Mutex},
+    thread,
+};
+
+/// Upper bound of threads walking a directory tree, more rarely help on disks.
+const MAX_WORKERS: usize = 8;
+
+/// Identity of a directory, to detect cycles through symbolic links.
+#[cfg(unix)]
+type DirId = (u64, u64);
+#[cfg(not(unix))]
+type DirId = PathBuf;
+
+#[cfg(unix)]
+fn dir_id(_path: &Path, metadata: &Metadata) -> Option<DirId> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn dir_id(path: &Path, _metadata: &Metadata) -> Option<DirId> {
+    fs::canonicalize(path).ok()
+}
+
+/// A directory and the directories containing it on the walked path, which must not
+/// be entered again below it.
+struct Ancestors {
+    id: DirId,
+    parent: Option<Arc<Ancestors>>,
+}
+
+impl Ancestors {
+    fn contains(&self, id: &DirId) -> bool {
+        let mut ancestor = Some(self);
+        while let Some(Ancestors {
+            id: ancestor_id,
+            parent,
+        }) = ancestor
+        {
+            if ancestor_id == id {
+                return true;
+            }
+            ancestor = parent.as_deref();
+        }
+        false
+    }
+}
+
+/// Directories waiting to be read with their ancestors, shared between the workers.
+struct Queue {
+    dirs: Vec<(PathBuf, Option<Arc<Ancestors>>)>,
+    /// Number of workers reading a directory, which may add more
+    busy: usize,
+    /// Set when `emit` returned `false`
+    stopped: bool,
+    skipped: Vec<(PathBuf, io::Error)>,
+}
+
+/// Recursively passes the files below `root` that are accepted to `emit`, together
+/// with their metadata, following symbolic links.
+///
+/// Directories are read in parallel, subdirectories are only entered if
+/// `descend` returns `true` for their path and whether they are a symbolic link.
+/// Links to a directory that contains them are skipped, so that cycles end, other
+/// directories are read once for every path leading to them. The order of the files
+/// is unspecified. The walk stops when `emit` returns `false`.
+///
+/// Directories that cannot be read are skipped and returned with the error, the walk
+/// continues with the others. Directories that do not exist, e.g. because they were
+/// removed during the walk, are skipped silently.
+pub(super) fn walk<D, F, E>(
+    root: &Path,
+    descend: D,
+    accept: F,
+    emit: E,
+) -> Vec<(PathBuf, io::Error)>
+where
+    D: Fn(&Path, bool) -> bool + Sync,
+    F: Fn(&Path) -> bool + Sync,
+    E: Fn(PathBuf, Metadata) -> bool + Sync,
+{
+    let ancestors = fs::metadata(root)
+        .ok()
+        .and_then(|metadata| dir_id(root, &metadata))
+        .map(|id| Arc::new(Ancestors { id, parent: None }));
+    let queue = Mutex::new(Queue {
+        dirs: vec![(root.to_path_buf(), ancestors)],
+        busy: 0,
+        stopped: false,
+        skipped: Vec::new(),
+    });
+    let wake = Condvar::new();
+    let workers = thread::available_parallelism().map_or(1, |n| n.get().min(MAX_WORKERS));
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| work(&queue, &wake, &descend, &accept, &emit));
+        }
+    });
+
+    let queue = queue.into_inner().expect("walker threads should not panic");
+    queue.skipped
+}
+
+fn work<D, F, E>(queue: &Mutex<Queue>, wake: &Condvar, descend: &D, accept: &F, emit: &E)
+where
+    D: Fn(&Path, bool) -> bool,
+    F: Fn(&Path) -> bool,
+    E: Fn(PathBuf, Metadata) -> bool,
+{
+    loop {
+        let (dir, ancestors) = {
+            let mut queue = queue
+                .lock()
+                .expect("another thread holding the lock paniced");
+            loop {
+                if queue.stopped {
+                    return;
+                }
+                if let Some(dir) = queue.dirs.pop() {
+                    queue.busy += 1;
+                    break dir;
+                }
+                if queue.busy == 0 {
+                    return;
+                }
+                queue = wake
+                    .wait(queue)
+                    .expect("another thread holding the lock paniced");
+            }
+        };
+
+        let mut dirs = Vec::new();
+        let result = read_dir(&dir, &ancestors, descend, accept, emit, &mut dirs);
+
+        let mut queue = queue
+            .lock()
+            .expect("another thread holding the lock paniced");
+        queue.busy -= 1;
+        match result {
+            Ok(true) => queue.dirs.append(&mut dirs),
+            Ok(false) => queue.stopped = true,
+            Err(e) => {
+                // The subdirectories found before the error are still walked
+                queue.dirs.append(&mut dirs);
+                if e.kind() != io::ErrorKind::NotFound {
+                    queue.skipped.push((dir, e));
+                }
+            }
+        }
+        wake.notify_all();
+    }
+}
+
+/// Reads a directory, adding the subdirectories to enter to `dirs`, and returns
+/// whether the walk continues.
+fn read_dir<D, F, E>(
+    dir: &Path,
+    ancestors: &Option<Arc<Ancestors>>,
+    descend: &D,
+    accept: &F,
+    emit: &E,
+    dirs: &mut Vec<(PathBuf, Option<Arc<Ancestors>>)>,
+) -> io::Result<bool>
+where
+    D: Fn(&Path, bool) -> bool,
+    F: Fn(&Path) -> bool,
+    E: Fn(PathBuf, Metadata) -> bool,
+{
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        // A single stat per entry, broken links are skipped
+        let Ok(metadata) = fs::metadata(&path) else {
+            continue;
+        };
+        if metadata.is_dir() {
+            let is_symlink = entry.file_type()?.is_symlink();
+            if !descend(&path, is_symlink) {
+                continue;
+            }
+            match dir_id(&path, &metadata) {
+                Some(id) if ancestors.as_ref().is_some_and(|a| a.contains(&id)) => {}
+                Some(id) => {
+                    let parent = ancestors.clone();
+                    dirs.push((path, Some(Arc::new(Ancestors { id, parent }))));
+                }
+                None => dirs.push((path, ancestors.clone())),
+            }
+        } else if metadata.is_file() && accept(&path) && !emit(path, metadata) {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pass::test_dir::TestDir;
+
+    #[test]
+    fn walk_finds_nested_files() {
+        let root = TestDir::new("walk");
+        for dir in ["a/b/c", "a/d", ".hidden"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        for file in [
+            "top.gpg",
+            "a/b/c/deep.gpg",
+            "a/d/other.txt",
+            ".hidden/skipped.gpg",
+        ] {
+            fs::write(root.join(file), b"").unwrap();
+        }
+
+        let found = Mutex::new(Vec::new());
+        assert!(walk(
+            &root,
+            |dir, _| dir.file_name().is_some_and(|name| name != ".hidden"),
+            |file| file.extension().is_some_and(|ext| ext == "gpg"),
+            |path, _| {
+                found.lock().unwrap().push(path);
+                true
+            },
+        )
+        .is_empty());
+        let mut found = found.into_inner().unwrap();
+        found.sort();
+        assert_eq!(found, [root.join("a/b/c/deep.gpg"), root.join("top.gpg")]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn walk_ends_symlink_cycles() {
+        let root = TestDir::new("cycle");
+        fs::create_dir_all(root.join("dir")).unwrap();
+        fs::write(root.join("dir/entry.gpg"), b"").unwrap();
+        std::os::unix::fs::symlink("..", root.join("dir/parent")).unwrap();
+
+        let found = Mutex::new(0);
+        assert!(walk(
+            &root,
+            |_, _| true,
+            |_| true,
+            |_, _| {
+                *found.lock().unwrap() += 1;
+                true
+            }
+        )
+        .is_empty());
+        assert_eq!(*found.lock().unwrap(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn walk_enters_linked_directories_on_every_path() {
+        let root = TestDir::new("linked");
+        fs::create_dir_all(root.join("real")).unwrap();
+        fs::write(root.join("real/entry.gpg"), b"").unwrap();
+        std::os::unix::fs::symlink("real", root.join("link")).unwrap();
+
+        // The same for every run, whichever worker reaches the directory first
+        for _ in 0..10 {
+            let found = Mutex::new(Vec::new());
+            assert!(walk(
+                &root,
+                |_, _| true,
+                |_| true,
+                |path, _| {
+                    found.lock().unwrap().push(path);
+                    true
+                }
+            )
+            .is_empty());
+            let mut found = found.into_inner().unwrap();
+            found.sort();
+            assert_eq!(
+                found,
+                [root.join("link/entry.gpg"), root.join("real/entry.gpg")]
+            );
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn walk_skips_unreadable_directories() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = TestDir::new("unreadable");
+        for dir in ["a", "locked", "z"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+            fs::write(root.join(dir).join("entry.gpg"), b"").unwrap();
+        }
+        fs::set_permissions(root.join("locked"), fs::Permissions::from_mode(0o000)).unwrap();
+        // Permissions do not restrict root
+        let readable = fs::read_dir(root.join("locked")).is_ok();
+
+        let found = Mutex::new(Vec::new());
+        let skipped = walk(
+            &root,
+            |_, _| true,
+            |_| true,
+            |path, _| {
+                found.lock().unwrap().push(path);
+                true
+            },
+        );
+        fs::set_permissions(root.join("locked"), fs::Permissions::from_mode(0o700)).unwrap();
+
+        let mut found = found.into_inner().unwrap();
+        found.sort();
+        let skipped: Vec<&PathBuf> = skipped.iter().map(|(dir, _)| dir).collect();
+        if readable {
+            assert_eq!(found.len(), 3);
+            assert!(skipped.is_empty());
+        } else {
+            assert_eq!(found, [root.join("a/entry.gpg"), root.join("z/entry.gpg")]);
+            assert_eq!(skipped, [&root.join("locked")]);
+        }
+    }
+}